@@ -1,4 +1,6 @@
-use s2_tilejson::{DrawType, LayerMetaData, LonLatBounds, Metadata, MetadataBuilder, Shape};
+use s2_tilejson::{
+    DrawType, LayerMetaData, LonLatBounds, Metadata, MetadataBuilder, Scheme, Shape,
+};
 use s2json::Face;
 
 fn main() {
@@ -8,7 +10,7 @@ fn main() {
     meta_builder.set_name("OSM".into());
     meta_builder.set_description("A free editable map of the whole world.".into());
     meta_builder.set_version("1.0.0".into());
-    meta_builder.set_scheme("fzxy".into()); // 'fzxy' | 'tfzxy' | 'xyz' | 'txyz' | 'tms'
+    meta_builder.set_scheme(Scheme::Fzxy); // 'fzxy' | 'tfzxy' | 'xyz' | 'txyz' | 'tms'
     meta_builder.set_type("vector".into()); // 'vector' | 'json' | 'raster' | 'raster-dem' | 'sensor' | 'markers'
     meta_builder.set_encoding("none".into()); // 'gz' | 'br' | 'none'
     meta_builder.add_attribution("OpenStreetMap", "https://www.openstreetmap.org/copyright/");
@@ -33,7 +35,7 @@ fn main() {
         shape: shape.clone(),
         m_shape: None,
     };
-    meta_builder.add_layer("water_lines", &layer);
+    meta_builder.add_layer("water_lines", &layer).unwrap_or_else(|e| panic!("ERROR: {e}"));
 
     // as you build tiles, add the tiles metadata:
     // WM:
@@ -0,0 +1,381 @@
+use s2_tilejson::{Face, Metadata, UnknownMetadata};
+use std::{
+    env, fs,
+    io::{self, Read, Write},
+    process::ExitCode,
+};
+
+const USAGE: &str = "\
+Usage: s2-tilejson <COMMAND> [ARGS]
+
+Commands:
+  inspect [FILE]                    Print a summary of the faces, zooms, layers and tile counts
+  validate [FILE]                   Check the metadata against the S2-TileJSON spec
+  convert [--to s2|mapbox] [FILE]   Convert between S2-TileJSON and Mapbox TileJSON [default: s2]
+  merge FILE FILE...                Merge multiple metadata files into one
+  diff FILE FILE                    List the JSON paths that differ between two metadata files
+
+FILE may be `-` or omitted to read from stdin. Mapbox TileJSON input is converted to S2-TileJSON
+first. Exits with 1 on invalid input, validation errors, merge conflicts or differences.
+";
+
+/// What a command printed and whether it succeeded
+struct Output {
+    stdout: String,
+    stderr: String,
+    success: bool,
+}
+impl Output {
+    fn ok(stdout: String) -> Output {
+        Output { stdout, stderr: String::new(), success: true }
+    }
+
+    fn error(stderr: String) -> Output {
+        Output { stdout: String::new(), stderr, success: false }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let output = run(&args, &mut |path| {
+        if path == "-" {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input).map(|_| input)
+        } else {
+            fs::read_to_string(path)
+        }
+        .map_err(|e| format!("{path}: {e}"))
+    });
+    let _ = io::stdout().write_all(output.stdout.as_bytes());
+    let _ = io::stderr().write_all(output.stderr.as_bytes());
+    if output.success { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
+
+/// Run a command. `read` loads a file, where `-` is stdin
+fn run(args: &[String], read: &mut dyn FnMut(&str) -> Result<String, String>) -> Output {
+    let Some((command, args)) = args.split_first() else {
+        return Output::error(USAGE.into());
+    };
+    let result = match command.as_str() {
+        "inspect" => single_input(args).and_then(|path| load(read, path)).map(|m| inspect(&m)),
+        "validate" => single_input(args).and_then(|path| load(read, path)).map(|m| validate(&m)),
+        "convert" => convert(args, read),
+        "merge" => merge(args, read),
+        "diff" => diff(args, read),
+        "help" | "--help" | "-h" => Ok(Output::ok(USAGE.into())),
+        _ => Err(format!("unknown command \"{command}\"\n\n{USAGE}")),
+    };
+    result.unwrap_or_else(|e| Output::error(format!("error: {e}\n")))
+}
+
+/// The path of a command that takes at most one input
+fn single_input(args: &[String]) -> Result<&str, String> {
+    match args {
+        [] => Ok("-"),
+        [path] => Ok(path),
+        _ => Err(format!("expected a single input, got {}", args.len())),
+    }
+}
+
+/// Read and parse an S2-TileJSON or Mapbox TileJSON file
+fn load(
+    read: &mut dyn FnMut(&str) -> Result<String, String>,
+    path: &str,
+) -> Result<Metadata, String> {
+    let input = read(path)?;
+    let meta: UnknownMetadata =
+        serde_json::from_str(&input).map_err(|e| format!("{path}: invalid metadata: {e}"))?;
+    Ok(meta.to_metadata())
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    let mut json = serde_json::to_string_pretty(value).unwrap_or_default();
+    json.push('\n');
+    json
+}
+
+fn inspect(meta: &Metadata) -> Output {
+    let mut out = String::new();
+    let mut line = |s: String| {
+        out.push_str(&s);
+        out.push('\n');
+    };
+    let scheme: &str = meta.scheme.into();
+    let source_type = serde_json::to_value(meta.r#type).unwrap_or_default();
+    let encoding: &str = meta.encoding.into();
+    line(format!("name: {}", meta.name));
+    line(format!("version: {}", meta.version));
+    line(format!("scheme: {scheme}"));
+    line(format!(
+        "type: {} ({}, encoding {encoding})",
+        source_type.as_str().unwrap_or_default(),
+        meta.extension
+    ));
+    let faces: Vec<String> = meta.faces.iter().map(|f| (*f as u8).to_string()).collect();
+    line(format!("faces: {}", if faces.is_empty() { "none".into() } else { faces.join(", ") }));
+    line(format!("zooms: {}-{}", meta.minzoom, meta.maxzoom));
+    let b = &meta.bounds;
+    line(format!("bounds: [{}, {}, {}, {}]", b.left, b.bottom, b.right, b.top));
+    let c = &meta.centerpoint;
+    line(format!("center: [{}, {}] at zoom {}", c.lon, c.lat, c.zoom));
+    let per_face: Vec<String> = (0..6)
+        .map(Face::from)
+        .filter(|f| meta.tilestats.get(*f) > 0)
+        .map(|f| format!("face {}: {}", f as u8, meta.tilestats.get(f)))
+        .collect();
+    if per_face.is_empty() {
+        line(format!("tiles: {}", meta.tilestats.total));
+    } else {
+        line(format!("tiles: {} ({})", meta.tilestats.total, per_face.join(", ")));
+    }
+    if let Some(stats) = &meta.detailed_tilestats {
+        for (zoom, z) in &stats.zooms {
+            line(format!(
+                "  zoom {zoom}: {} tiles, {} bytes (min {}, max {}, avg {:.1})",
+                z.tiles, z.bytes, z.min_bytes, z.max_bytes, z.avg_bytes
+            ));
+        }
+    }
+    line(format!("layers: {}", meta.layers.len()));
+    for (name, layer) in &meta.layers {
+        let draw_types: Vec<String> = layer.draw_types.iter().map(|d| format!("{d:?}")).collect();
+        line(format!(
+            "  {name} zooms {}-{} [{}]",
+            layer.minzoom,
+            layer.maxzoom,
+            draw_types.join(", ")
+        ));
+        line(format!("    shape: {}", serde_json::to_string(&layer.shape).unwrap_or_default()));
+        if let Some(m_shape) = &layer.m_shape {
+            line(format!("    mShape: {}", serde_json::to_string(m_shape).unwrap_or_default()));
+        }
+    }
+    Output::ok(out)
+}
+
+fn validate(meta: &Metadata) -> Output {
+    let issues = meta.validate();
+    let mut stderr = String::new();
+    for issue in &issues {
+        stderr.push_str(&format!("{issue}\n"));
+    }
+    let errors = issues.iter().filter(|i| i.is_error()).count();
+    Output {
+        stdout: if errors == 0 { "valid\n".into() } else { String::new() },
+        stderr,
+        success: errors == 0,
+    }
+}
+
+fn convert(
+    args: &[String],
+    read: &mut dyn FnMut(&str) -> Result<String, String>,
+) -> Result<Output, String> {
+    let (target, args) = match args {
+        [flag, target, rest @ ..] if flag == "--to" => (target.as_str(), rest),
+        _ => ("s2", args),
+    };
+    let meta = load(read, single_input(args)?)?;
+    match target {
+        "s2" => Ok(Output::ok(to_json(&meta))),
+        "mapbox" => {
+            let (mapbox, lost) = meta.to_mapbox();
+            let mut stderr = String::new();
+            for path in lost {
+                stderr.push_str(&format!("warning: {path} can't be represented in TileJSON\n"));
+            }
+            Ok(Output { stdout: to_json(&mapbox), stderr, success: true })
+        }
+        _ => Err(format!("unknown conversion target \"{target}\", expected s2 or mapbox")),
+    }
+}
+
+fn merge(
+    args: &[String],
+    read: &mut dyn FnMut(&str) -> Result<String, String>,
+) -> Result<Output, String> {
+    if args.len() < 2 {
+        return Err("merge needs at least two inputs".into());
+    }
+    let mut merged = load(read, &args[0])?;
+    let mut stderr = String::new();
+    for path in &args[1..] {
+        for conflict in merged.merge(&load(read, path)?) {
+            stderr.push_str(&format!("conflict in {path}: {conflict}\n"));
+        }
+    }
+    Ok(Output { stdout: to_json(&merged), success: stderr.is_empty(), stderr })
+}
+
+fn diff(
+    args: &[String],
+    read: &mut dyn FnMut(&str) -> Result<String, String>,
+) -> Result<Output, String> {
+    let [a, b] = args else {
+        return Err("diff needs exactly two inputs".into());
+    };
+    let a = serde_json::to_value(load(read, a)?).map_err(|e| e.to_string())?;
+    let b = serde_json::to_value(load(read, b)?).map_err(|e| e.to_string())?;
+    let mut out = String::new();
+    diff_values(&mut out, "$", &a, &b);
+    Ok(Output { success: out.is_empty(), stdout: out, stderr: String::new() })
+}
+
+/// Write a line for every path where `a` and `b` differ
+fn diff_values(out: &mut String, path: &str, a: &serde_json::Value, b: &serde_json::Value) {
+    use serde_json::Value;
+    match (a, b) {
+        (Value::Object(a_map), Value::Object(b_map)) => {
+            let mut keys: Vec<&String> = a_map.keys().chain(b_map.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let identifier = key.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                let child =
+                    if identifier { format!("{path}.{key}") } else { format!("{path}[\"{key}\"]") };
+                let (a, b) = (a_map.get(key), b_map.get(key));
+                match (a, b) {
+                    (Some(a), Some(b)) => diff_values(out, &child, a, b),
+                    (Some(a), None) => out.push_str(&format!("- {child}: {a}\n")),
+                    (None, Some(b)) => out.push_str(&format!("+ {child}: {b}\n")),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if a != b => out.push_str(&format!("~ {path}: {a} -> {b}\n")),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    const S2: &str = r#"{
+        "s2tilejson": "1.0.0",
+        "version": "1.0.0",
+        "name": "OSM",
+        "scheme": "fzxy",
+        "description": "A free editable map of the whole world.",
+        "type": "vector",
+        "extension": "pbf",
+        "faces": [0, 1],
+        "bounds": [-120, -20, 44, 72],
+        "minzoom": 0,
+        "maxzoom": 13,
+        "centerpoint": { "lon": -38, "lat": 26, "zoom": 6 },
+        "attributions": {},
+        "layers": {
+            "water_lines": {
+                "minzoom": 0,
+                "maxzoom": 13,
+                "draw_types": [2],
+                "shape": { "class": "string" }
+            }
+        },
+        "tilestats": { "total": 2, "0": 1, "1": 1 },
+        "vector_layers": []
+    }"#;
+
+    fn exec(args: &[&str], files: &[(&str, &str)]) -> Output {
+        let files: BTreeMap<String, String> =
+            files.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        run(&args, &mut |path| files.get(path).cloned().ok_or(format!("{path}: not found")))
+    }
+
+    #[test]
+    fn test_inspect() {
+        let out = exec(&["inspect"], &[("-", S2)]);
+        assert!(out.success);
+        assert!(out.stdout.contains("faces: 0, 1\n"));
+        assert!(out.stdout.contains("zooms: 0-13\n"));
+        assert!(out.stdout.contains("tiles: 2 (face 0: 1, face 1: 1)\n"));
+        assert!(out.stdout.contains("  water_lines zooms 0-13 [Lines]\n"));
+        assert!(out.stdout.contains("    shape: {\"class\":\"string\"}\n"));
+
+        let out = exec(&["inspect", "missing.json"], &[]);
+        assert!(!out.success);
+        assert_eq!(out.stderr, "error: missing.json: not found\n");
+        let out = exec(&["inspect"], &[("-", "not json")]);
+        assert!(!out.success);
+        assert!(out.stderr.starts_with("error: -: invalid metadata"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let out = exec(&["validate", "a.json"], &[("a.json", S2)]);
+        assert!(out.success, "{}", out.stderr);
+        assert_eq!(out.stdout, "valid\n");
+
+        let invalid = S2.replace(r#""maxzoom": 13,"#, r#""maxzoom": 31,"#);
+        let out = exec(&["validate", "a.json"], &[("a.json", &invalid)]);
+        assert!(!out.success);
+        assert!(out.stderr.contains("$.maxzoom"));
+    }
+
+    #[test]
+    fn test_convert() {
+        let out = exec(&["convert", "--to", "mapbox"], &[("-", S2)]);
+        assert!(out.success);
+        let mapbox: serde_json::Value = serde_json::from_str(&out.stdout).unwrap();
+        assert_eq!(mapbox["tilejson"], "3.0.0");
+        assert!(out.stderr.contains("warning: $.faces can't be represented in TileJSON"));
+
+        let out = exec(&["convert", "-"], &[("-", &out.stdout)]);
+        assert!(out.success);
+        let meta: Metadata = serde_json::from_str(&out.stdout).unwrap();
+        assert_eq!(meta.name, "OSM");
+
+        let out = exec(&["convert", "--to", "ogc"], &[("-", S2)]);
+        assert!(!out.success);
+    }
+
+    #[test]
+    fn test_merge() {
+        let other = S2.replace(r#""maxzoom": 13,"#, r#""maxzoom": 15,"#);
+        let out = exec(&["merge", "a", "b"], &[("a", S2), ("b", &other)]);
+        assert!(out.success, "{}", out.stderr);
+        let meta: Metadata = serde_json::from_str(&out.stdout).unwrap();
+        assert_eq!(meta.maxzoom, 15);
+        assert_eq!(meta.tilestats.total, 4);
+
+        let other = S2.replace(r#""scheme": "fzxy""#, r#""scheme": "tfzxy""#);
+        let out = exec(&["merge", "a", "b"], &[("a", S2), ("b", &other)]);
+        assert!(!out.success);
+        assert!(out.stderr.contains("conflict in b: $.scheme"));
+
+        assert!(!exec(&["merge", "a"], &[("a", S2)]).success);
+    }
+
+    #[test]
+    fn test_diff() {
+        let out = exec(&["diff", "a", "b"], &[("a", S2), ("b", S2)]);
+        assert!(out.success);
+        assert_eq!(out.stdout, "");
+
+        let other = S2.replace(r#""name": "OSM","#, r#""name": "OpenStreetMap","#).replace(
+            r#""shape": { "class": "string" }"#,
+            r#""shape": { "class": "string", "rank": "u64" }"#,
+        );
+        let out = exec(&["diff", "a", "b"], &[("a", S2), ("b", &other)]);
+        assert!(!out.success);
+        assert_eq!(
+            out.stdout,
+            "+ $.layers.water_lines.shape.rank: \"u64\"\n~ $.name: \"OSM\" -> \"OpenStreetMap\"\n"
+        );
+        let out = exec(&["diff", "a", "b"], &[("a", S2), ("b", r#"{ "tilejson": "3.0.0" }"#)]);
+        assert!(out.stdout.contains("~ $.tilestats[\"0\"]: 1 -> 0\n"));
+    }
+
+    #[test]
+    fn test_usage() {
+        assert!(!exec(&[], &[]).success);
+        assert!(exec(&["--help"], &[]).success);
+        let out = exec(&["publish"], &[]);
+        assert!(!out.success);
+        assert!(out.stderr.starts_with("error: unknown command \"publish\""));
+    }
+}
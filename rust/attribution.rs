@@ -0,0 +1,387 @@
+use crate::Attributions;
+use alloc::{format, string::String, vec::Vec};
+
+/// Parse a legacy HTML `attribution` string into [`Attributions`].
+///
+/// Every `<a href="...">name</a>` becomes a `name -> href` entry. Single, double and unquoted
+/// `href` values are supported, as are any number of anchors. Text right after an anchor up to
+/// the next `,`, `|`, `;` or `·` is part of its name (`<a>OSM</a> contributors`), while other
+/// text outside of anchors is kept as an entry with an empty href unless it is only a
+/// separator. HTML entities (`&amp;`, `&copy;`, `&#169;`, `&#xA9;`, ...) are decoded, copyright
+/// signs are dropped from every name and any other tags are stripped.
+///
+/// ```
+/// use s2_tilejson::parse_attribution_html;
+///
+/// let attributions = parse_attribution_html(
+///     "<a href=\"https://osm.org\">OSM</a> &copy; <a href='https://mapbox.com'>Mapbox</a>",
+/// );
+/// assert_eq!(attributions.get("OSM").map(String::as_str), Some("https://osm.org"));
+/// assert_eq!(attributions.get("Mapbox").map(String::as_str), Some("https://mapbox.com"));
+/// ```
+pub fn parse_attribution_html(html: &str) -> Attributions {
+    let mut attributions = Attributions::new();
+    let mut text = String::new();
+    // the open anchor and the last closed anchor, which takes any text up to the next separator
+    let mut anchor: Option<(String, String)> = None;
+    let mut closed: Option<(String, String)> = None;
+    let mut rest = html;
+    while !rest.is_empty() {
+        let Some(open) = rest.find('<') else {
+            push_text(&mut attributions, &mut anchor, &mut closed, &mut text, rest);
+            break;
+        };
+        push_text(&mut attributions, &mut anchor, &mut closed, &mut text, &rest[..open]);
+        rest = &rest[open..];
+        // an unterminated tag is dropped
+        let Some(close) = rest.find('>') else { break };
+        let tag = &rest[1..close];
+        rest = &rest[close + 1..];
+
+        let name = tag_name(tag);
+        if name == "a" && !tag.starts_with('/') {
+            // an unclosed anchor ends when the next one starts
+            insert(&mut attributions, anchor.take());
+            insert(&mut attributions, closed.take());
+            insert_text(&mut attributions, &mut text);
+            anchor = Some((attribute(tag, "href").unwrap_or_default(), String::new()));
+        } else if name == "a" {
+            if anchor.is_some() {
+                insert(&mut attributions, closed.take());
+                closed = anchor.take();
+            }
+        } else if anchor.is_none() {
+            // any other tag separates plain text
+            insert(&mut attributions, closed.take());
+            insert_text(&mut attributions, &mut text);
+        }
+    }
+    insert(&mut attributions, anchor.take());
+    insert(&mut attributions, closed.take());
+    insert_text(&mut attributions, &mut text);
+
+    attributions
+}
+
+/// Render attributions as a safe HTML string for the legacy `attribution` field.
+///
+/// Entries with an href become `<a href="...">name</a>` links while entries without one (or with
+/// an unsafe `javascript:`, `data:` or `vbscript:` href) are rendered as plain text. Everything is
+/// escaped and entries are joined by ` | `. The inverse of [`parse_attribution_html`].
+pub fn attributions_to_html(attributions: &Attributions) -> String {
+    attributions
+        .iter()
+        .map(|(name, href)| {
+            if is_safe_href(href) {
+                format!("<a href=\"{}\">{}</a>", escape_html(href), escape_html(name))
+            } else {
+                escape_html(name)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// Escape text for use inside HTML content or a quoted attribute
+pub fn escape_html(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Decode the HTML entities of a string. Unknown entities are kept as is
+pub fn decode_html_entities(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| decode_entity(&rest[1..=end]).map(|c| (c, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    if let Some(num) = entity.strip_prefix('#') {
+        let code = match num.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match entity {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "mdash" => '—',
+        "ndash" => '–',
+        "middot" => '·',
+        _ => return None,
+    })
+}
+
+/// Append decoded text to the open anchor name, or to the name of the last closed anchor up to
+/// the next list separator, or else to the plain text buffer
+fn push_text(
+    attributions: &mut Attributions,
+    anchor: &mut Option<(String, String)>,
+    closed: &mut Option<(String, String)>,
+    text: &mut String,
+    raw: &str,
+) {
+    let decoded = decode_html_entities(raw);
+    if let Some((_, name)) = anchor {
+        name.push_str(&decoded);
+        return;
+    }
+    let mut raw = decoded.as_str();
+    if let Some((_, name)) = closed {
+        let Some(end) = raw.find(LIST_SEPARATORS) else {
+            name.push_str(raw);
+            return;
+        };
+        name.push_str(&raw[..end]);
+        insert(attributions, closed.take());
+        raw = &raw[end..];
+    }
+    text.push_str(raw);
+}
+
+fn insert(attributions: &mut Attributions, anchor: Option<(String, String)>) {
+    let Some((href, name)) = anchor else { return };
+    let name = normalize(&name);
+    if !name.is_empty() {
+        attributions.insert(name, href);
+    }
+}
+
+/// Add the plain text buffer as entries without an href. The text is split on list separators
+/// and anything that is only a separator like `©` is ignored
+fn insert_text(attributions: &mut Attributions, text: &mut String) {
+    for part in normalize(text).split(LIST_SEPARATORS) {
+        let name = part.trim_matches(is_separator);
+        if !name.is_empty() {
+            attributions.insert(name.into(), String::new());
+        }
+    }
+    text.clear();
+}
+
+/// Characters splitting plain text into separate entries
+const LIST_SEPARATORS: [char; 4] = [',', '|', ';', '·'];
+
+/// Drop copyright signs and collapse whitespace
+fn normalize(text: &str) -> String {
+    text.replace('©', " ").split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '|' | ',' | ';' | '-' | '–' | '—' | '·' | '/' | '&')
+}
+
+/// The lowercase name of a tag such as `a` for `<a href="...">` or `</A>`
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '/')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// Find the value of an attribute inside of a tag, supporting any quoting style
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find(name) {
+        let start = from + pos;
+        from = start + name.len();
+        // must be a whole attribute name
+        if !lower[..start].ends_with(|c: char| c.is_whitespace()) {
+            continue;
+        }
+        let after = tag[from..].trim_start();
+        let Some(value) = after.strip_prefix('=') else { continue };
+        let value = value.trim_start();
+        let raw = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value = &value[1..];
+                &value[..value.find(quote).unwrap_or(value.len())]
+            }
+            _ => value.split(|c: char| c.is_whitespace()).next().unwrap_or_default(),
+        };
+        return Some(decode_html_entities(raw.trim()));
+    }
+    None
+}
+
+fn is_safe_href(href: &str) -> bool {
+    let lower = href.trim().to_ascii_lowercase();
+    !lower.is_empty()
+        && !lower.starts_with("javascript:")
+        && !lower.starts_with("data:")
+        && !lower.starts_with("vbscript:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attributions(entries: &[(&str, &str)]) -> Attributions {
+        let mut attributions = Attributions::new();
+        for (name, href) in entries {
+            attributions.insert((*name).into(), (*href).into());
+        }
+        attributions
+    }
+
+    #[test]
+    fn test_parse_quotes() {
+        assert_eq!(
+            parse_attribution_html("<a href='https://openstreetmap.org'>OSM contributors</a>"),
+            attributions(&[("OSM contributors", "https://openstreetmap.org")])
+        );
+        assert_eq!(
+            parse_attribution_html("<a href=\"https://osm.org\">OSM</a>"),
+            attributions(&[("OSM", "https://osm.org")])
+        );
+        assert_eq!(
+            parse_attribution_html("<A class=x HREF = https://osm.org target=_blank>OSM</A>"),
+            attributions(&[("OSM", "https://osm.org")])
+        );
+        // data-href is not href
+        assert_eq!(
+            parse_attribution_html("<a data-href='x' href='https://osm.org'>OSM</a>"),
+            attributions(&[("OSM", "https://osm.org")])
+        );
+    }
+
+    #[test]
+    fn test_parse_multiple() {
+        assert_eq!(
+            parse_attribution_html(
+                "<a href=\"https://osm.org\">OSM</a> &copy; <a href=\"https://mapbox.com/?a=1&amp;b=2\">\
+                 Mapbox</a> | <a href='https://s2maps.io'><b>S2</b> Maps</a>"
+            ),
+            attributions(&[
+                ("OSM", "https://osm.org"),
+                ("Mapbox", "https://mapbox.com/?a=1&b=2"),
+                ("S2 Maps", "https://s2maps.io"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_plain_text() {
+        assert_eq!(
+            parse_attribution_html("© OpenStreetMap contributors"),
+            attributions(&[("OpenStreetMap contributors", "")])
+        );
+        assert_eq!(
+            parse_attribution_html(
+                "&copy; <a href='https://osm.org/copyright'>OpenStreetMap</a> contributors, \
+                 Natural Earth<br/>Tiles &#169; Esri &amp; Co"
+            ),
+            attributions(&[
+                ("OpenStreetMap contributors", "https://osm.org/copyright"),
+                ("Natural Earth", ""),
+                ("Tiles Esri & Co", ""),
+            ])
+        );
+        // text between anchors that is only a copyright sign stays out of the names
+        assert_eq!(
+            parse_attribution_html("<a href='a'>A</a> &copy; <a href='b'>B</a> ©"),
+            attributions(&[("A", "a"), ("B", "b")])
+        );
+        assert_eq!(
+            parse_attribution_html("<a href='a'>&copy; A</a> | Tiles"),
+            attributions(&[("A", "a"), ("Tiles", "")])
+        );
+        assert_eq!(parse_attribution_html(""), Attributions::new());
+        assert_eq!(parse_attribution_html(" &copy; | "), Attributions::new());
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        // unclosed anchors and tags do not panic
+        assert_eq!(
+            parse_attribution_html("<a href='https://osm.org'>OSM <a href=\"https://b.org\">B"),
+            attributions(&[("OSM", "https://osm.org"), ("B", "https://b.org")])
+        );
+        assert_eq!(parse_attribution_html("<a href='x"), Attributions::new());
+        assert_eq!(parse_attribution_html("<"), Attributions::new());
+        assert_eq!(parse_attribution_html("x <a"), attributions(&[("x", "")]));
+        assert_eq!(parse_attribution_html("a < b"), attributions(&[("a", "")]));
+    }
+
+    #[test]
+    fn test_decode_html_entities() {
+        assert_eq!(decode_html_entities("&lt;b&gt; &quot;x&quot; &#39;y&apos;"), "<b> \"x\" 'y'");
+        assert_eq!(decode_html_entities("&#xA9; &#169; &copy;"), "© © ©");
+        assert_eq!(decode_html_entities("a & b &unknown; &#xZZ; &"), "a & b &unknown; &#xZZ; &");
+    }
+
+    #[test]
+    fn test_attributions_to_html() {
+        let escaped = Attributions::from([
+            ("OpenStreetMap".into(), "https://www.openstreetmap.org/copyright/".into()),
+            ("<S2> & \"co\"".into(), "https://opens2.com/?a=1&b=2".into()),
+        ]);
+        assert_eq!(
+            attributions_to_html(&escaped),
+            "<a href=\"https://opens2.com/?a=1&amp;b=2\">&lt;S2&gt; &amp; &quot;co&quot;</a> | <a \
+             href=\"https://www.openstreetmap.org/copyright/\">OpenStreetMap</a>"
+        );
+        // plain text and unsafe links are not anchors
+        let entries = attributions(&[
+            ("Natural Earth", ""),
+            ("evil", "JavaScript:alert(1)"),
+            ("OSM", "https://osm.org"),
+        ]);
+        let html = attributions_to_html(&entries);
+        assert_eq!(html, "Natural Earth | <a href=\"https://osm.org\">OSM</a> | evil");
+        // and back again, minus the unsafe link
+        assert_eq!(
+            parse_attribution_html(&html),
+            attributions(&[("Natural Earth", ""), ("evil", ""), ("OSM", "https://osm.org")])
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let entries = attributions(&[
+            ("OSM & friends", "https://osm.org/?a=1&b=2"),
+            ("Mapbox", "https://www.mapbox.com/about/maps/"),
+            ("S2 \"Maps\"", "https://s2maps.io"),
+        ]);
+        assert_eq!(parse_attribution_html(&attributions_to_html(&entries)), entries);
+    }
+}
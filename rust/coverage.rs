@@ -0,0 +1,519 @@
+use crate::{FACES, Face, Metadata, TileBounds};
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec::Vec,
+};
+use serde::{Deserialize, Serialize};
+
+/// Runs of existing tiles for a single zoom. Each key is the row-major index (`y * 2^zoom + x`)
+/// of the first tile in a run and each value is the index of the last tile in that run
+pub type TileRuns = BTreeMap<u64, u64>;
+
+/// # Tile Coverage
+///
+/// ## Description
+/// A sparse index of exactly which tiles exist. Unlike `wmbounds` and `s2bounds` which store a
+/// single rectangle per zoom, a dataset covering two islands will not report the ocean between
+/// them as existing.
+///
+/// Zooms are stored as run-length encoded row-major tile indices. Zooms that are missing were
+/// either never recorded or were dropped because they grew too large, in which case the
+/// rectangle form should be used instead.
+///
+/// ## Usage
+/// - [`TileCoverage::insert_wm`]: Record a WM tile
+/// - [`TileCoverage::insert_s2`]: Record an S2 tile
+/// - [`TileCoverage::contains_wm`]: Check if a WM tile was recorded
+/// - [`TileCoverage::contains_s2`]: Check if an S2 tile was recorded
+/// - [`TileCoverage::has_zoom_wm`]: Check if a WM zoom is tracked
+/// - [`TileCoverage::has_zoom_s2`]: Check if an S2 face-zoom is tracked
+/// - [`TileCoverage::run_count`]: The total number of runs stored
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct TileCoverage {
+    /// WM tile runs at each zoom
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub wm: BTreeMap<u8, TileRuns>,
+    /// S2 tile runs at each face and zoom
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub s2: BTreeMap<Face, BTreeMap<u8, TileRuns>>,
+}
+impl TileCoverage {
+    /// Record a WM tile
+    pub fn insert_wm(&mut self, zoom: u8, x: u64, y: u64) {
+        insert_run(self.wm.entry(zoom).or_default(), tile_index(zoom, x, y));
+    }
+
+    /// Record an S2 tile
+    pub fn insert_s2(&mut self, face: Face, zoom: u8, x: u64, y: u64) {
+        let runs = self.s2.entry(face).or_default().entry(zoom).or_default();
+        insert_run(runs, tile_index(zoom, x, y));
+    }
+
+    /// Check if a WM tile was recorded. Always false for zooms that are not tracked
+    pub fn contains_wm(&self, zoom: u8, x: u64, y: u64) -> bool {
+        self.wm.get(&zoom).is_some_and(|runs| contains_run(runs, tile_index(zoom, x, y)))
+    }
+
+    /// Check if an S2 tile was recorded. Always false for zooms that are not tracked
+    pub fn contains_s2(&self, face: Face, zoom: u8, x: u64, y: u64) -> bool {
+        self.s2
+            .get(&face)
+            .and_then(|zooms| zooms.get(&zoom))
+            .is_some_and(|runs| contains_run(runs, tile_index(zoom, x, y)))
+    }
+
+    /// Check if a WM zoom is tracked
+    pub fn has_zoom_wm(&self, zoom: u8) -> bool {
+        self.wm.contains_key(&zoom)
+    }
+
+    /// Check if an S2 face-zoom is tracked
+    pub fn has_zoom_s2(&self, face: Face, zoom: u8) -> bool {
+        self.s2.get(&face).is_some_and(|zooms| zooms.contains_key(&zoom))
+    }
+
+    /// The total number of runs stored across every zoom
+    pub fn run_count(&self) -> usize {
+        self.wm.values().map(BTreeMap::len).sum::<usize>()
+            + self.s2.values().flat_map(BTreeMap::values).map(BTreeMap::len).sum::<usize>()
+    }
+
+    /// Returns true if nothing has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.wm.is_empty() && self.s2.is_empty()
+    }
+
+    /// Drop the zooms with the most runs until at most `max_runs` runs remain, so the rectangle
+    /// form is used for them instead
+    pub(crate) fn cap(&mut self, max_runs: usize) {
+        while self.run_count() > max_runs {
+            if self.drop_largest_zoom().is_none() {
+                break;
+            }
+        }
+    }
+
+    /// Drop the zoom with the most runs so the rectangle form is used for it instead.
+    /// Returns the face (`None` for WM) and zoom that was dropped.
+    pub(crate) fn drop_largest_zoom(&mut self) -> Option<CoverageZoom> {
+        let wm = self.wm.iter().map(|(zoom, runs)| (runs.len(), None, *zoom));
+        let s2 = self.s2.iter().flat_map(|(face, zooms)| {
+            zooms.iter().map(move |(zoom, runs)| (runs.len(), Some(*face), *zoom))
+        });
+        let (_, face, zoom) = wm.chain(s2).max()?;
+        match face {
+            None => {
+                self.wm.remove(&zoom);
+            }
+            Some(face) => {
+                let zooms = self.s2.get_mut(&face)?;
+                zooms.remove(&zoom);
+                if zooms.is_empty() {
+                    self.s2.remove(&face);
+                }
+            }
+        }
+        Some((face, zoom))
+    }
+}
+
+/// A WM zoom (`None`) or an S2 face-zoom
+pub(crate) type CoverageZoom = (Option<Face>, u8);
+
+impl TileCoverage {
+    fn zoom_runs(&self, (face, zoom): CoverageZoom) -> Option<&TileRuns> {
+        match face {
+            None => self.wm.get(&zoom),
+            Some(face) => self.s2.get(&face)?.get(&zoom),
+        }
+    }
+
+    fn zoom_runs_mut(&mut self, (face, zoom): CoverageZoom) -> &mut TileRuns {
+        match face {
+            None => self.wm.entry(zoom).or_default(),
+            Some(face) => self.s2.entry(face).or_default().entry(zoom).or_default(),
+        }
+    }
+
+    pub(crate) fn remove_zoom(&mut self, (face, zoom): CoverageZoom) {
+        match face {
+            None => {
+                self.wm.remove(&zoom);
+            }
+            Some(face) => {
+                if let Some(zooms) = self.s2.get_mut(&face) {
+                    zooms.remove(&zoom);
+                    if zooms.is_empty() {
+                        self.s2.remove(&face);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Every WM and S2 face-zoom that holds tiles according to `wmbounds` and `s2bounds`
+pub(crate) fn tile_zooms(meta: &Metadata) -> BTreeSet<CoverageZoom> {
+    let wm = meta.wmbounds.keys().map(|zoom| (None, *zoom));
+    let s2 =
+        FACES.iter().flat_map(|face| meta.s2bounds.get(*face).keys().map(|z| (Some(*face), *z)));
+    wm.chain(s2).collect()
+}
+
+/// Merge the coverage of another tileset into `ours`.
+///
+/// A zoom stays tracked only if every side holding tiles at that zoom tracks it, otherwise the
+/// merged coverage could claim that tiles of the untracked side do not exist. Returns the zooms
+/// that can no longer be tracked.
+pub(crate) fn merge_coverage(
+    ours: &mut TileCoverage,
+    ours_zooms: &BTreeSet<CoverageZoom>,
+    theirs: Option<&TileCoverage>,
+    theirs_zooms: &BTreeSet<CoverageZoom>,
+) -> Vec<CoverageZoom> {
+    let mut dropped = Vec::new();
+    for key in theirs_zooms {
+        let ours_untracked = ours_zooms.contains(key) && ours.zoom_runs(*key).is_none();
+        match theirs.and_then(|theirs| theirs.zoom_runs(*key)) {
+            Some(runs) if !ours_untracked => {
+                let ours_runs = ours.zoom_runs_mut(*key);
+                for (start, end) in runs {
+                    insert_range(ours_runs, *start, *end);
+                }
+            }
+            _ => {
+                ours.remove_zoom(*key);
+                dropped.push(*key);
+            }
+        }
+    }
+    dropped
+}
+
+/// Row-major index of a tile
+fn tile_index(zoom: u8, x: u64, y: u64) -> u64 {
+    (y << zoom) | x
+}
+
+/// Add an index to a set of runs, merging neighbouring runs
+fn insert_run(runs: &mut TileRuns, index: u64) {
+    insert_range(runs, index, index);
+}
+
+/// Add an inclusive range of indices to a set of runs, merging overlapping and neighbouring runs
+fn insert_range(runs: &mut TileRuns, mut start: u64, mut end: u64) {
+    if let Some((prev_start, prev_end)) = runs.range(..=start).next_back()
+        && prev_end.saturating_add(1) >= start
+    {
+        start = *prev_start;
+        end = end.max(*prev_end);
+    }
+    while let Some((next_start, next_end)) = runs.range(start..).next().map(|(s, e)| (*s, *e))
+        && next_start <= end.saturating_add(1)
+    {
+        runs.remove(&next_start);
+        end = end.max(next_end);
+    }
+    runs.insert(start, end);
+}
+
+fn contains_run(runs: &TileRuns, index: u64) -> bool {
+    runs.range(..=index).next_back().is_some_and(|(_, end)| *end >= index)
+}
+
+impl Metadata {
+    /// Check if a Web Mercator tile exists according to `minzoom`, `maxzoom`, `coverage` and
+    /// `wmbounds`.
+    ///
+    /// If `coverage` tracks the zoom, it is used directly. If `wmbounds` has no entry for the
+    /// zoom, the closest parent zoom is used instead. If no parent exists, the closest child zoom
+    /// is shifted up. Empty `wmbounds` means every tile in the zoom range is assumed to exist.
+    pub fn has_tile_wm(&self, zoom: u8, x: u64, y: u64) -> bool {
+        if !self.in_zoom_range(zoom) || !in_range(zoom, x, y) {
+            return false;
+        }
+        match &self.coverage {
+            Some(coverage) if coverage.has_zoom_wm(zoom) => coverage.contains_wm(zoom, x, y),
+            _ => zoom_bounds_contain(&self.wmbounds, zoom, x, y),
+        }
+    }
+
+    /// Check if an S2 tile exists according to `minzoom`, `maxzoom`, `faces`, `coverage` and
+    /// `s2bounds`.
+    ///
+    /// Missing zooms are resolved the same way as [`Metadata::has_tile_wm`]. An empty `faces`
+    /// list is treated as all faces having data, per the spec default.
+    pub fn has_tile_s2(&self, face: Face, zoom: u8, x: u64, y: u64) -> bool {
+        if !self.in_zoom_range(zoom)
+            || !in_range(zoom, x, y)
+            || !(self.faces.is_empty() || self.faces.contains(&face))
+        {
+            return false;
+        }
+        match &self.coverage {
+            Some(c) if c.has_zoom_s2(face, zoom) => c.contains_s2(face, zoom, x, y),
+            _ => zoom_bounds_contain(self.s2bounds.get(face), zoom, x, y),
+        }
+    }
+
+    fn in_zoom_range(&self, zoom: u8) -> bool {
+        zoom >= self.minzoom && zoom <= self.maxzoom
+    }
+}
+
+/// Ensure the tile position fits inside the zoom
+fn in_range(zoom: u8, x: u64, y: u64) -> bool {
+    zoom < 64 && x >> zoom == 0 && y >> zoom == 0
+}
+
+/// Check a tile against per zoom bounds, falling back to the nearest parent or child zoom
+fn zoom_bounds_contain(bounds: &BTreeMap<u8, TileBounds>, zoom: u8, x: u64, y: u64) -> bool {
+    if bounds.is_empty() {
+        return true;
+    }
+    if let Some((parent_zoom, bbox)) = bounds.range(..=zoom).next_back() {
+        let shift = zoom - parent_zoom;
+        return bbox_contains(bbox, x >> shift, y >> shift);
+    }
+    // only deeper zooms are known, so shift their bounds up to this zoom
+    let (child_zoom, bbox) = bounds.iter().next().expect("bounds are not empty");
+    let shift = child_zoom - zoom;
+    let parent = TileBounds {
+        left: bbox.left >> shift,
+        bottom: bbox.bottom >> shift,
+        right: bbox.right >> shift,
+        top: bbox.top >> shift,
+    };
+    bbox_contains(&parent, x, y)
+}
+
+fn bbox_contains(bbox: &TileBounds, x: u64, y: u64) -> bool {
+    x >= bbox.left && x <= bbox.right && y >= bbox.bottom && y <= bbox.top
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BBox, LonLatBounds, MetadataBuilder};
+    use alloc::vec;
+
+    #[test]
+    fn test_has_tile_wm() {
+        let meta = Metadata {
+            minzoom: 1,
+            maxzoom: 6,
+            wmbounds: BTreeMap::from([
+                (2, BBox::new(1, 1, 2, 2)),
+                (3, BBox::new(2, 2, 4, 5)),
+                (5, BBox::new(10, 8, 12, 9)),
+            ]),
+            ..Default::default()
+        };
+        // zoom range
+        assert!(!meta.has_tile_wm(0, 0, 0));
+        assert!(!meta.has_tile_wm(7, 40, 32));
+        // out of the zoom
+        assert!(!meta.has_tile_wm(2, 4, 0));
+        // exact bounds
+        assert!(meta.has_tile_wm(2, 1, 1));
+        assert!(meta.has_tile_wm(2, 2, 2));
+        assert!(!meta.has_tile_wm(2, 0, 1));
+        assert!(!meta.has_tile_wm(2, 3, 2));
+        assert!(meta.has_tile_wm(3, 4, 5));
+        assert!(!meta.has_tile_wm(3, 5, 5));
+        // zoom 4 falls back to zoom 3: [2,2,4,5] covers x 4..=9, y 4..=11
+        assert!(meta.has_tile_wm(4, 9, 11));
+        assert!(!meta.has_tile_wm(4, 10, 11));
+        assert!(!meta.has_tile_wm(4, 3, 4));
+        // zoom 6 falls back to zoom 5
+        assert!(meta.has_tile_wm(6, 25, 19));
+        assert!(!meta.has_tile_wm(6, 26, 20));
+        // zoom 1 has no parent so the zoom 2 child is shifted up
+        assert!(meta.has_tile_wm(1, 0, 0));
+        assert!(meta.has_tile_wm(1, 1, 1));
+
+        // no bounds means everything in the zoom range exists
+        let meta = Metadata { minzoom: 0, maxzoom: 3, ..Default::default() };
+        assert!(meta.has_tile_wm(3, 7, 7));
+        assert!(!meta.has_tile_wm(3, 8, 7));
+    }
+
+    #[test]
+    fn test_has_tile_s2() {
+        let mut meta = Metadata {
+            minzoom: 0,
+            maxzoom: 10,
+            faces: vec![Face::Face1, Face::Face2],
+            ..Default::default()
+        };
+        meta.s2bounds.get_mut(Face::Face1).insert(5, BBox::new(22, 17, 23, 18));
+        assert!(meta.has_tile_s2(Face::Face1, 5, 22, 17));
+        assert!(meta.has_tile_s2(Face::Face1, 5, 23, 18));
+        assert!(!meta.has_tile_s2(Face::Face1, 5, 24, 18));
+        // parent fallback
+        assert!(meta.has_tile_s2(Face::Face1, 7, 95, 75));
+        assert!(!meta.has_tile_s2(Face::Face1, 7, 96, 75));
+        // child fallback
+        assert!(meta.has_tile_s2(Face::Face1, 0, 0, 0));
+        assert!(meta.has_tile_s2(Face::Face1, 4, 11, 8));
+        assert!(!meta.has_tile_s2(Face::Face1, 4, 10, 8));
+        // face 2 is listed but has no bounds
+        assert!(meta.has_tile_s2(Face::Face2, 3, 1, 1));
+        // face 3 is not listed
+        assert!(!meta.has_tile_s2(Face::Face3, 3, 1, 1));
+        // zoom range
+        assert!(!meta.has_tile_s2(Face::Face2, 11, 0, 0));
+
+        // empty faces means all faces
+        meta.faces = vec![];
+        assert!(meta.has_tile_s2(Face::Face3, 3, 1, 1));
+    }
+
+    #[test]
+    fn test_tile_runs() {
+        let mut runs = TileRuns::new();
+        insert_run(&mut runs, 5);
+        insert_run(&mut runs, 7);
+        assert_eq!(runs, BTreeMap::from([(5, 5), (7, 7)]));
+        insert_run(&mut runs, 6);
+        assert_eq!(runs, BTreeMap::from([(5, 7)]));
+        insert_run(&mut runs, 4);
+        insert_run(&mut runs, 8);
+        insert_run(&mut runs, 6);
+        assert_eq!(runs, BTreeMap::from([(4, 8)]));
+        insert_run(&mut runs, 0);
+        insert_run(&mut runs, u64::MAX);
+        assert_eq!(runs, BTreeMap::from([(0, 0), (4, 8), (u64::MAX, u64::MAX)]));
+        assert!(contains_run(&runs, 0));
+        assert!(!contains_run(&runs, 1));
+        assert!(contains_run(&runs, 8));
+        assert!(!contains_run(&runs, 9));
+
+        let mut runs = TileRuns::from([(0, 2), (5, 6), (9, 9), (20, 30)]);
+        insert_range(&mut runs, 3, 9);
+        assert_eq!(runs, TileRuns::from([(0, 9), (20, 30)]));
+        insert_range(&mut runs, 22, 25);
+        assert_eq!(runs, TileRuns::from([(0, 9), (20, 30)]));
+        insert_range(&mut runs, 12, 40);
+        assert_eq!(runs, TileRuns::from([(0, 9), (12, 40)]));
+    }
+
+    #[test]
+    fn test_merge_coverage() {
+        let wm =
+            |zooms: &[u8]| -> BTreeSet<CoverageZoom> { zooms.iter().map(|z| (None, *z)).collect() };
+        let mut ours = TileCoverage::default();
+        ours.insert_wm(2, 0, 0);
+        ours.insert_wm(3, 0, 0);
+        let mut theirs = TileCoverage::default();
+        theirs.insert_wm(2, 1, 0);
+        theirs.insert_wm(4, 1, 0);
+        theirs.insert_s2(Face::Face3, 1, 1, 1);
+        let theirs_zooms =
+            BTreeSet::from([(None, 2), (None, 4), (None, 5), (Some(Face::Face3), 1)]);
+        // zoom 1 holds our tiles but is untracked, zoom 3 is not touched by theirs
+        let dropped = merge_coverage(&mut ours, &wm(&[1, 2, 3]), Some(&theirs), &theirs_zooms);
+        assert_eq!(dropped, vec![(None, 5)]);
+        assert_eq!(ours.wm.get(&2), Some(&TileRuns::from([(0, 1)])));
+        assert!(ours.has_zoom_wm(3));
+        assert!(ours.contains_wm(4, 1, 0));
+        assert!(ours.contains_s2(Face::Face3, 1, 1, 1));
+
+        // theirs has tiles at a zoom we do not track
+        let theirs_zooms = wm(&[1, 2]);
+        let dropped = merge_coverage(&mut ours, &wm(&[1, 2, 3]), Some(&theirs), &theirs_zooms);
+        assert_eq!(dropped, vec![(None, 1)]);
+        assert!(ours.has_zoom_wm(2));
+
+        // no coverage at all on their side
+        let dropped = merge_coverage(&mut ours, &wm(&[2, 3]), None, &wm(&[3]));
+        assert_eq!(dropped, vec![(None, 3)]);
+        assert!(!ours.has_zoom_wm(3));
+    }
+
+    #[test]
+    fn test_tile_coverage() {
+        let mut coverage = TileCoverage::default();
+        assert!(coverage.is_empty());
+        // two islands at zoom 4
+        coverage.insert_wm(4, 1, 1);
+        coverage.insert_wm(4, 2, 1);
+        coverage.insert_wm(4, 12, 9);
+        coverage.insert_s2(Face::Face2, 3, 0, 7);
+        assert!(!coverage.is_empty());
+        assert_eq!(coverage.run_count(), 3);
+        assert!(coverage.contains_wm(4, 1, 1));
+        assert!(coverage.contains_wm(4, 2, 1));
+        assert!(!coverage.contains_wm(4, 7, 5));
+        assert!(coverage.contains_wm(4, 12, 9));
+        assert!(!coverage.contains_wm(5, 12, 9));
+        assert!(coverage.contains_s2(Face::Face2, 3, 0, 7));
+        assert!(!coverage.contains_s2(Face::Face1, 3, 0, 7));
+        assert!(coverage.has_zoom_wm(4));
+        assert!(!coverage.has_zoom_wm(3));
+        assert!(coverage.has_zoom_s2(Face::Face2, 3));
+        assert!(!coverage.has_zoom_s2(Face::Face2, 4));
+
+        let json = serde_json::to_string(&coverage).unwrap();
+        assert_eq!(json, r#"{"wm":{"4":{"17":18,"156":156}},"s2":{"2":{"3":{"56":56}}}}"#);
+        let back: TileCoverage = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, coverage);
+
+        assert_eq!(coverage.drop_largest_zoom(), Some((None, 4)));
+        assert_eq!(coverage.drop_largest_zoom(), Some((Some(Face::Face2), 3)));
+        assert_eq!(coverage.drop_largest_zoom(), None);
+        assert!(coverage.is_empty());
+    }
+
+    #[test]
+    fn test_builder_coverage() {
+        let ll = LonLatBounds::new(0., 0., 1., 1.);
+        let mut builder = MetadataBuilder::default();
+        builder.enable_coverage(3);
+        builder.add_tile_wm(4, 1, 1, &ll);
+        builder.add_tile_wm(4, 12, 9, &ll);
+        builder.add_tile_wm(3, 6, 4, &ll);
+        builder.add_tile_s2(Face::Face1, 2, 0, 0, &ll);
+        let meta = builder.commit();
+
+        // the largest zoom was degraded to the rectangle form
+        let coverage = meta.coverage.clone().unwrap();
+        assert!(!coverage.has_zoom_wm(4));
+        assert!(coverage.has_zoom_wm(3));
+        assert!(coverage.has_zoom_s2(Face::Face1, 2));
+        assert_eq!(meta.wmbounds.get(&4), Some(&BBox::new(1, 1, 12, 9)));
+
+        let meta = Metadata { minzoom: 0, maxzoom: 5, ..meta };
+        // rectangle form at zoom 4 so the ocean between the islands "exists"
+        assert!(meta.has_tile_wm(4, 7, 5));
+        // coverage at zoom 3
+        assert!(meta.has_tile_wm(3, 6, 4));
+        assert!(!meta.has_tile_wm(3, 5, 4));
+        assert!(meta.has_tile_s2(Face::Face1, 2, 0, 0));
+        assert!(!meta.has_tile_s2(Face::Face1, 2, 1, 0));
+
+        // a zoom that can't fit on its own is dropped
+        let mut builder = MetadataBuilder::default();
+        builder.enable_coverage(1);
+        builder.add_tile_wm(4, 1, 1, &ll);
+        builder.add_tile_wm(4, 3, 1, &ll);
+        builder.add_tile_wm(4, 5, 1, &ll);
+        let meta = builder.commit();
+        assert_eq!(meta.coverage, None);
+
+        // serializes as an optional field
+        let json = serde_json::to_string(&Metadata::default()).unwrap();
+        assert!(!json.contains("coverage"));
+        let meta = Metadata {
+            coverage: Some(TileCoverage {
+                wm: BTreeMap::from([(0, TileRuns::from([(0, 0)]))]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&meta).unwrap();
+        assert!(json.contains(r#""coverage":{"wm":{"0":{"0":0}}}"#));
+        assert_eq!(serde_json::from_str::<Metadata>(&json).unwrap(), meta);
+    }
+}
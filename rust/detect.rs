@@ -0,0 +1,173 @@
+use crate::{MapboxTileJSONMetadata, Metadata, UnknownMetadata, mbtiles_rows_to_mapbox};
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use serde::{Deserialize, Deserializer, de::Error};
+use serde_json::{Map, Value};
+
+/// The format and version recognised by [`UnknownMetadata`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DetectedSpec {
+    /// S2-TileJSON, keyed on `s2tilejson`. Contains its version
+    S2TileJSON(String),
+    /// Mapbox TileJSON, keyed on `tilejson`. Contains its version
+    TileJSON(String),
+    /// Tippecanoe metadata without a `tilejson` key. Contains the `generator` if present
+    Tippecanoe(Option<String>),
+    /// The `name`/`value` rows of an MBTiles `metadata` table as a JSON object
+    MBTiles,
+}
+
+impl UnknownMetadata {
+    /// The format and version the input was recognised as
+    pub fn detected_spec(&self) -> DetectedSpec {
+        match self {
+            UnknownMetadata::Metadata(m) => DetectedSpec::S2TileJSON(m.s2tilejson.clone()),
+            UnknownMetadata::Mapbox(m) if !m.tilejson.is_empty() => {
+                DetectedSpec::TileJSON(m.tilejson.clone())
+            }
+            UnknownMetadata::Mapbox(m) => DetectedSpec::Tippecanoe(m.get_extra("generator")),
+            UnknownMetadata::MBTiles(_) => DetectedSpec::MBTiles,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for UnknownMetadata {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let Value::Object(object) = Value::deserialize(deserializer)? else {
+            return Err(D::Error::custom("metadata must be a JSON object"));
+        };
+        if object.contains_key("s2tilejson") {
+            let meta: Metadata = serde_json::from_value(Value::Object(object))
+                .map_err(|e| D::Error::custom(format!("invalid S2-TileJSON: {e}")))?;
+            Ok(UnknownMetadata::Metadata(Box::new(meta)))
+        } else if object.contains_key("tilejson") || is_tippecanoe(&object) {
+            let meta: MapboxTileJSONMetadata = serde_json::from_value(Value::Object(object))
+                .map_err(|e| D::Error::custom(format!("invalid TileJSON: {e}")))?;
+            Ok(UnknownMetadata::Mapbox(Box::new(meta)))
+        } else if is_mbtiles(&object) {
+            let rows: Vec<(String, String)> = object
+                .into_iter()
+                .map(|(name, value)| match value {
+                    Value::String(value) => (name, value),
+                    value => (name, value.to_string()),
+                })
+                .collect();
+            let meta = mbtiles_rows_to_mapbox(rows).map_err(D::Error::custom)?;
+            Ok(UnknownMetadata::MBTiles(Box::new(meta)))
+        } else {
+            Err(D::Error::custom(
+                "unrecognized metadata: expected an `s2tilejson` or `tilejson` key, tippecanoe \
+                 metadata or MBTiles metadata rows",
+            ))
+        }
+    }
+}
+
+/// Tippecanoe writes TileJSON-like metadata (e.g. in PMTiles) without a `tilejson` key
+fn is_tippecanoe(object: &Map<String, Value>) -> bool {
+    let generator = object.get("generator").and_then(Value::as_str);
+    generator.is_some_and(|g| g.starts_with("tippecanoe"))
+        || object.get("vector_layers").is_some_and(Value::is_array)
+        || object.get("tilestats").is_some_and(|t| t.get("layerCount").is_some())
+}
+
+/// MBTiles rows store every value as a string, including the `json` row and the comma
+/// separated `bounds` and `center`
+fn is_mbtiles(object: &Map<String, Value>) -> bool {
+    object.get("json").is_some_and(Value::is_string)
+        || ["format", "bounds", "center", "minzoom", "maxzoom"]
+            .iter()
+            .any(|key| object.get(*key).is_some_and(Value::is_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrawType, Scheme};
+
+    #[test]
+    fn test_detect_s2() {
+        let json = serde_json::to_string(&Metadata::default()).unwrap();
+        let meta: UnknownMetadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(meta.detected_spec(), DetectedSpec::S2TileJSON("1.0.0".into()));
+        assert_eq!(meta.to_metadata(), Metadata::default());
+    }
+
+    #[test]
+    fn test_detect_tilejson() {
+        let meta: UnknownMetadata = serde_json::from_str(
+            r#"{ "tilejson": "2.2.0", "tiles": ["https://tiles.org/{z}/{x}/{y}.png"] }"#,
+        )
+        .unwrap();
+        assert_eq!(meta.detected_spec(), DetectedSpec::TileJSON("2.2.0".into()));
+        let meta = meta.to_metadata();
+        assert_eq!(meta.tiles, Some(["https://tiles.org/{z}/{x}/{y}.png".into()].into()));
+        assert!(!meta.faces.is_empty());
+    }
+
+    #[test]
+    fn test_detect_tippecanoe() {
+        let meta: UnknownMetadata = serde_json::from_str(
+            r#"{
+                "name": "roads",
+                "generator": "tippecanoe v2.5.0",
+                "vector_layers": [{ "id": "roads", "fields": {} }],
+                "tilestats": {
+                    "layerCount": 1,
+                    "layers": [{ "layer": "roads", "count": 1, "geometry": "LineString" }]
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            meta.detected_spec(),
+            DetectedSpec::Tippecanoe(Some("tippecanoe v2.5.0".into()))
+        );
+        assert_eq!(meta.to_metadata().layers["roads"].draw_types, [DrawType::Lines]);
+
+        let meta: UnknownMetadata = serde_json::from_str(r#"{ "vector_layers": [] }"#).unwrap();
+        assert_eq!(meta.detected_spec(), DetectedSpec::Tippecanoe(None));
+    }
+
+    #[test]
+    fn test_detect_mbtiles() {
+        let meta: UnknownMetadata = serde_json::from_str(
+            r#"{
+                "name": "roads",
+                "format": "pbf",
+                "bounds": "-10,-20,30,40",
+                "center": "10,10,3",
+                "minzoom": "0",
+                "maxzoom": 14,
+                "json": "{\"vector_layers\":[{\"id\":\"roads\",\"fields\":{}}]}"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(meta.detected_spec(), DetectedSpec::MBTiles);
+        let meta = meta.to_metadata();
+        assert_eq!(meta.scheme, Scheme::Xyz);
+        assert_eq!((meta.minzoom, meta.maxzoom), (0, 14));
+        assert_eq!(meta.bounds.right, 30.);
+        assert_eq!(meta.tiles, None);
+        assert!(meta.layers.contains_key("roads"));
+
+        let err = serde_json::from_str::<UnknownMetadata>(r#"{ "bounds": "1,2" }"#).unwrap_err();
+        assert!(err.to_string().starts_with("invalid MBTiles metadata row bounds"));
+    }
+
+    #[test]
+    fn test_detect_unknown() {
+        let err = serde_json::from_str::<UnknownMetadata>(r#"{ "name": "roads" }"#).unwrap_err();
+        assert!(err.to_string().starts_with("unrecognized metadata: expected an `s2tilejson`"));
+        let err = serde_json::from_str::<UnknownMetadata>("[1, 2]").unwrap_err();
+        assert_eq!(err.to_string(), "metadata must be a JSON object");
+        let err =
+            serde_json::from_str::<UnknownMetadata>(r#"{ "s2tilejson": "1.0.0", "faces": 3 }"#)
+                .unwrap_err();
+        assert!(err.to_string().starts_with("invalid S2-TileJSON"));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    DrawType, LayerMetaData, MValue, MValueCompatible, MetadataBuilder, Shape, ShapeConflict,
+    VectorFeature, VectorGeometry, VectorPoint, shape_from_value, shape_union,
+};
+use alloc::vec::Vec;
+
+impl MetadataBuilder {
+    /// Add a feature written to a layer at a zoom, so the layer metadata always matches the tiles
+    /// actually written.
+    ///
+    /// The feature's draw type is added to the layer `draw_types`, its properties are unioned
+    /// into the layer `shape`, the M-Values of its geometry are unioned into the layer `mShape`
+    /// and the layer zoom range is widened to include `zoom`. The layer is created on first use.
+    /// With [`MetadataBuilder::enable_detailed_stats`] the feature is also counted.
+    /// Returns a [`ShapeConflict`] and leaves the layer untouched if the feature's properties or
+    /// M-Values can't be reconciled with the layer.
+    pub fn add_feature<M: Clone, P: MValueCompatible, D: MValueCompatible>(
+        &mut self,
+        layer: &str,
+        zoom: u8,
+        feature: &VectorFeature<M, P, D>,
+    ) -> Result<(), ShapeConflict> {
+        let properties: MValue = feature.properties.clone().into();
+        let shape = shape_from_value(&properties).map_err(|e| e.prefixed("shape"))?;
+        let m_shape = geometry_m_shape(&feature.geometry).map_err(|e| e.prefixed("mShape"))?;
+        let draw_type = DrawType::from(feature);
+        let layer_meta = LayerMetaData {
+            description: None,
+            minzoom: zoom,
+            maxzoom: zoom,
+            draw_types: Vec::from([draw_type]),
+            shape,
+            m_shape,
+        };
+        self.add_layer(layer, &layer_meta)?;
+        self.add_layer_features(layer, draw_type, 1);
+        Ok(())
+    }
+}
+
+/// Union the shapes of every M-Value found in the geometry. `None` if no point has an M-Value
+fn geometry_m_shape<D: MValueCompatible>(
+    geometry: &VectorGeometry<D>,
+) -> Result<Option<Shape>, ShapeConflict> {
+    let mut m_shape: Option<Shape> = None;
+    for point in geometry_points(geometry) {
+        let Some(m) = &point.m else { continue };
+        let shape = shape_from_value(&m.clone().into())?;
+        m_shape = Some(match m_shape {
+            None => shape,
+            Some(prev) => shape_union(&prev, &shape)?,
+        });
+    }
+    Ok(m_shape)
+}
+
+/// Every point of a geometry
+fn geometry_points<D: MValueCompatible>(geometry: &VectorGeometry<D>) -> Vec<&VectorPoint<D>> {
+    match geometry {
+        VectorGeometry::Point(g) => Vec::from([&g.coordinates]),
+        VectorGeometry::MultiPoint(g) => g.coordinates.iter().collect(),
+        VectorGeometry::LineString(g) => g.coordinates.iter().collect(),
+        VectorGeometry::MultiLineString(g) => g.coordinates.iter().flatten().collect(),
+        VectorGeometry::Polygon(g) => g.coordinates.iter().flatten().collect(),
+        VectorGeometry::MultiPolygon(g) => g.coordinates.iter().flatten().flatten().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Properties, VectorLayer};
+    use alloc::{collections::BTreeMap, vec};
+
+    fn feature(
+        geometry: VectorGeometry<MValue>,
+        properties: &str,
+    ) -> VectorFeature<(), Properties, MValue> {
+        VectorFeature {
+            properties: serde_json::from_str(properties).unwrap(),
+            geometry,
+            ..Default::default()
+        }
+    }
+
+    fn shape(json: &str) -> Shape {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn point_m(x: f64, m: &str) -> VectorPoint<MValue> {
+        VectorPoint { m: Some(serde_json::from_str(m).unwrap()), ..VectorPoint::from_xy(x, 0.) }
+    }
+
+    #[test]
+    fn test_add_feature() {
+        let mut builder = MetadataBuilder::default();
+        let point = VectorGeometry::new_point(VectorPoint::from_xy(0., 0.), None);
+        let line = VectorGeometry::new_linestring(
+            vec![point_m(0., r#"{ "speed": 2 }"#), point_m(1., r#"{ "speed": -1, "kind": "a" }"#)],
+            None,
+        );
+        builder
+            .add_feature("roads", 5, &feature(point, r#"{ "class": "stop", "rank": 1 }"#))
+            .unwrap();
+        builder.add_feature("roads", 9, &feature(line, r#"{ "rank": -3, "lanes": 2 }"#)).unwrap();
+        builder
+            .add_feature(
+                "water",
+                3,
+                &feature(
+                    VectorGeometry::new_polygon(vec![vec![VectorPoint::from_xy(0., 0.)]], None),
+                    r#"{}"#,
+                ),
+            )
+            .unwrap();
+        let meta = builder.commit();
+
+        assert_eq!((meta.minzoom, meta.maxzoom), (3, 9));
+        assert_eq!(
+            meta.layers["roads"],
+            LayerMetaData {
+                description: None,
+                minzoom: 5,
+                maxzoom: 9,
+                draw_types: vec![DrawType::Points, DrawType::Lines],
+                shape: shape(r#"{ "class": "string", "rank": "i64", "lanes": "u64" }"#),
+                m_shape: Some(shape(r#"{ "speed": "i64", "kind": "string" }"#)),
+            }
+        );
+        assert_eq!(
+            meta.layers["water"],
+            LayerMetaData {
+                minzoom: 3,
+                maxzoom: 3,
+                draw_types: vec![DrawType::Polygons],
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            meta.vector_layers[0],
+            VectorLayer {
+                id: "roads".into(),
+                description: None,
+                minzoom: Some(5),
+                maxzoom: Some(9),
+                fields: BTreeMap::from([
+                    ("class".into(), "String".into()),
+                    ("lanes".into(), "Number".into()),
+                    ("rank".into(), "Number".into()),
+                ]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_add_feature_conflict() {
+        let mut builder = MetadataBuilder::default();
+        let point = || VectorGeometry::new_point(VectorPoint::from_xy(0., 0.), None);
+        builder.add_feature("roads", 5, &feature(point(), r#"{ "class": "stop" }"#)).unwrap();
+        let err = builder.add_feature("roads", 6, &feature(point(), r#"{ "class": { "a": 1 } }"#));
+        assert_eq!(err.unwrap_err().path, "shape.class");
+        let line = VectorGeometry::new_linestring(
+            vec![point_m(0., r#"{ "speed": 2 }"#), point_m(1., r#"{ "speed": "fast" }"#)],
+            None,
+        );
+        let err = builder.add_feature("roads", 7, &feature(line, r#"{}"#));
+        assert_eq!(err.unwrap_err().path, "mShape.speed");
+        // the layer was left untouched
+        let meta = builder.commit();
+        assert_eq!(meta.layers["roads"].maxzoom, 5);
+        assert_eq!(meta.layers["roads"].m_shape, None);
+    }
+}
@@ -0,0 +1,757 @@
+use crate::{Metadata, ValidationCode, is_tippecanoe_tilestats, is_valid_version};
+use alloc::{
+    collections::{BTreeMap, BTreeSet, btree_set},
+    format,
+    string::String,
+    vec::Vec,
+};
+use core::{cell::RefCell, fmt, iter::Enumerate, slice};
+use serde::{
+    Deserialize,
+    de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor},
+    forward_to_deserialize_any,
+};
+use serde_json::{Map, Value, map};
+
+/// Reasons [`Metadata::parse_lenient`] refuses an input
+#[derive(Debug)]
+pub enum LenientParseError {
+    /// The input is not valid JSON
+    Json(serde_json::Error),
+    /// The input is valid JSON but not an object
+    NotAnObject,
+    /// A required key is missing or invalid. Contains the JSON path of the key
+    InvalidRequired(String),
+}
+impl fmt::Display for LenientParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LenientParseError::Json(e) => write!(f, "invalid JSON: {e}"),
+            LenientParseError::NotAnObject => f.write_str("metadata must be a JSON object"),
+            LenientParseError::InvalidRequired(path) => {
+                write!(f, "required key {path} is missing or invalid")
+            }
+        }
+    }
+}
+
+impl Metadata {
+    /// Parse metadata following the spec rule that invalid values for optional keys MUST be
+    /// treated as if they were not present.
+    ///
+    /// Recovery follows the serde definitions of the structs: an invalid value falls back to
+    /// its default, or discards its parent if the parent requires it (e.g. a layer's `minzoom`).
+    /// Versions, zooms and lon-lat bounds breaking the spec are discarded as well, along with
+    /// unknown keys below the top level. The JSON paths of the discarded values are returned
+    /// alongside the result. Parsing still fails if the required `s2tilejson` key is missing or
+    /// invalid.
+    pub fn parse_lenient(input: &str) -> Result<(Metadata, Vec<String>), LenientParseError> {
+        let mut value: Value = serde_json::from_str(input).map_err(LenientParseError::Json)?;
+        if !value.is_object() {
+            return Err(LenientParseError::NotAnObject);
+        }
+        match value.get("s2tilejson") {
+            Some(Value::String(version)) if is_valid_version(version) => {}
+            _ => return Err(LenientParseError::InvalidRequired("$.s2tilejson".into())),
+        }
+
+        // invalid values are discarded while deserializing, only the spec checks need a rerun
+        let state = State::default();
+        // split the tilestats by layout like the Deserialize impl, recovering inside of them
+        let tilestats = value.as_object_mut().and_then(|object| object.remove("tilestats"));
+        let (s2_stats, tippecanoe_stats) = match &tilestats {
+            Some(stats) if is_tippecanoe_tilestats(stats) => {
+                (None, state.lenient(stats, "$.tilestats"))
+            }
+            Some(stats) => (state.lenient(stats, "$.tilestats"), None),
+            None => (None, None),
+        };
+        loop {
+            let mut meta: Metadata = match state.deserialize(Some(&value), "$") {
+                Ok(meta) => meta,
+                Err(Error::Missing(field)) => {
+                    return Err(LenientParseError::InvalidRequired(format!("$.{field}")));
+                }
+                Err(_) => return Err(LenientParseError::InvalidRequired("$".into())),
+            };
+            let mut skip = state.skip.borrow_mut();
+            let skipped = skip.len();
+            skip.extend(
+                meta.validate()
+                    .into_iter()
+                    .filter(|issue| {
+                        matches!(
+                            issue.code,
+                            ValidationCode::InvalidVersion
+                                | ValidationCode::ZoomOutOfRange
+                                | ValidationCode::BoundsOutOfRange
+                        )
+                    })
+                    .map(|issue| issue.path),
+            );
+            if skip.len() == skipped {
+                meta.tilestats = s2_stats.unwrap_or_default();
+                meta.tippecanoe_tilestats = meta.tippecanoe_tilestats.or(tippecanoe_stats);
+                return Ok((meta, state.discarded()));
+            }
+        }
+    }
+}
+
+/// Errors of the lenient deserializers. Missing fields are kept apart so they can be filled
+#[derive(Debug)]
+enum Error {
+    Json(serde_json::Error),
+    Missing(&'static str),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Json(e) => e.fmt(f),
+            Error::Missing(field) => write!(f, "missing field `{field}`"),
+        }
+    }
+}
+impl de::StdError for Error {}
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Json(de::Error::custom(msg))
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::Missing(field)
+    }
+}
+
+/// Bookkeeping shared by the deserializers of a lenient parse
+#[derive(Default)]
+struct State {
+    /// Paths of the values to treat as not present
+    skip: RefCell<BTreeSet<String>>,
+    /// Missing required fields to fill with an empty value, by the path of their struct
+    fill: RefCell<BTreeMap<String, BTreeSet<&'static str>>>,
+    /// Path of the innermost value that failed to deserialize
+    failed: RefCell<Option<String>>,
+    /// Paths of the skipped values and the ignored unknown keys
+    discarded: RefCell<BTreeSet<String>>,
+}
+impl State {
+    /// Deserialize the value at `path`, a missing value if `None`. Invalid values inside of it
+    /// are skipped and missing fields filled until it succeeds. Fails, leaving `path` as the
+    /// failed path, if the value itself is invalid.
+    fn deserialize<'a, T: Deserialize<'a>>(
+        &'a self,
+        value: Option<&'a Value>,
+        path: &str,
+    ) -> Result<T, Error> {
+        loop {
+            self.failed.take();
+            let res = match value {
+                Some(value) => T::deserialize(Lenient { value, path: path.into(), state: self }),
+                None => T::deserialize(Missing),
+            };
+            let err = match res {
+                Ok(res) => return Ok(res),
+                Err(err) => err,
+            };
+            let failed = self.failed.replace(Some(path.into())).unwrap_or_else(|| path.into());
+            let retry = match err {
+                Error::Missing(field) if failed == path && value.is_some() => {
+                    self.fill.borrow_mut().entry(failed).or_default().insert(field)
+                }
+                _ => failed != path && self.skip.borrow_mut().insert(failed),
+            };
+            if !retry {
+                return Err(err);
+            }
+        }
+    }
+
+    /// Deserialize the value at `path`, discarding it if invalid
+    fn lenient<'a, T: Deserialize<'a>>(&'a self, value: &'a Value, path: &str) -> Option<T> {
+        let res = self.deserialize(Some(value), path);
+        if res.is_err() {
+            self.discard(path.into());
+        }
+        res.ok()
+    }
+
+    /// Record the path of a failed value unless a value inside of it already failed
+    fn track<T>(&self, path: &str, res: Result<T, Error>) -> Result<T, Error> {
+        if res.is_err() {
+            self.failed.borrow_mut().get_or_insert_with(|| path.into());
+        }
+        res
+    }
+
+    /// Returns true, recording the path, if the value should be treated as not present
+    fn skips(&self, path: &str) -> bool {
+        let skips = self.skip.borrow().contains(path);
+        if skips {
+            self.discarded.borrow_mut().insert(path.into());
+        }
+        skips
+    }
+
+    /// Treat the failed value at `path` as not present from now on
+    fn discard(&self, path: String) {
+        self.failed.take();
+        self.discarded.borrow_mut().insert(path.clone());
+        self.skip.borrow_mut().insert(path);
+    }
+
+    /// The discarded paths, leaving out those inside of another discarded value
+    fn discarded(&self) -> Vec<String> {
+        let discarded = self.discarded.borrow();
+        discarded
+            .iter()
+            .filter(|path| {
+                !path.match_indices(['.', '[']).any(|(i, _)| discarded.contains(&path[..i]))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// A deserializer over a JSON value that tracks the path of each value and hides skipped values
+struct Lenient<'a> {
+    value: &'a Value,
+    path: String,
+    state: &'a State,
+}
+impl<'a> Lenient<'a> {
+    fn visit_object<V: Visitor<'a>>(
+        self,
+        object: &'a Map<String, Value>,
+        fields: bool,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let fill = self.state.fill.borrow().get(&self.path).cloned().unwrap_or_default();
+        visitor.visit_map(LenientMap {
+            entries: object.iter(),
+            fill: fill.into_iter(),
+            value: None,
+            // the top level holds the fields of Metadata
+            fields: fields || self.path == "$",
+            path: self.path,
+            state: self.state,
+        })
+    }
+
+    fn visit_array<V: Visitor<'a>>(
+        self,
+        array: &'a [Value],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let mut seq =
+            LenientSeq { elements: array.iter().enumerate(), path: self.path, state: self.state };
+        let res = visitor.visit_seq(&mut seq)?;
+        let state = seq.state;
+        let rest = seq.elements.filter(|(i, _)| !state.skips(&format!("{}[{i}]", seq.path)));
+        match rest.count() {
+            0 => Ok(res),
+            _ => Err(de::Error::invalid_length(array.len(), &"fewer elements in array")),
+        }
+    }
+}
+
+macro_rules! forward_to_value {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.value.$method(visitor).map_err(Error::Json)
+        }
+    )*};
+}
+
+impl<'a> Deserializer<'a> for Lenient<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Object(object) => self.visit_object(object, false, visitor),
+            Value::Array(array) => self.visit_array(array, visitor),
+            value => value.deserialize_any(visitor).map_err(Error::Json),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Array(array) => self.visit_array(array, visitor),
+            value => value.deserialize_seq(visitor).map_err(Error::Json),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'a>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Object(object) => self.visit_object(object, false, visitor),
+            value => value.deserialize_map(visitor).map_err(Error::Json),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'a>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Object(object) => self.visit_object(object, true, visitor),
+            value => value.deserialize_struct(name, fields, visitor).map_err(Error::Json),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'a>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.value.deserialize_enum(name, variants, visitor).map_err(Error::Json)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'a>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.value.deserialize_unit_struct(name, visitor).map_err(Error::Json)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, Error> {
+        // unknown keys of a struct
+        self.state.discarded.borrow_mut().insert(self.path);
+        visitor.visit_unit()
+    }
+
+    forward_to_value! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_unit
+        deserialize_identifier
+    }
+}
+
+/// The entries of a JSON object, followed by the missing fields to fill. Struct fields get
+/// `.key` paths, map entries `["key"]` paths. An invalid map entry is dropped in place
+struct LenientMap<'a> {
+    entries: map::Iter<'a>,
+    fill: btree_set::IntoIter<&'static str>,
+    value: Option<(String, Option<&'a Value>)>,
+    fields: bool,
+    path: String,
+    state: &'a State,
+}
+impl<'a> LenientMap<'a> {
+    /// The next entry that is not skipped, with its path. Filled fields have no value
+    fn next_entry_value(&mut self) -> Option<(&'a str, String, Option<&'a Value>)> {
+        while let Some((key, value)) = self.entries.next() {
+            let path = self.path_of(key);
+            if !self.state.skips(&path) {
+                return Some((key, path, Some(value)));
+            }
+        }
+        let field = self.fill.next()?;
+        Some((field, self.path_of(field), None))
+    }
+
+    fn path_of(&self, key: &str) -> String {
+        let is_field = self.fields
+            && key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        match is_field {
+            true => format!("{}.{key}", self.path),
+            false => format!("{}[\"{key}\"]", self.path),
+        }
+    }
+}
+impl<'a> MapAccess<'a> for LenientMap<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'a>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some((key, path, value)) = self.next_entry_value() else {
+            return Ok(None);
+        };
+        let res = self.state.track(&path, seed.deserialize(Key(key)));
+        self.value = Some((path, value));
+        res.map(Some)
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'a>>(&mut self, seed: S) -> Result<S::Value, Error> {
+        let (path, value) = self.value.take().expect("next_value_seed called before next_key_seed");
+        let res = match value {
+            Some(value) => {
+                seed.deserialize(Lenient { value, path: path.clone(), state: self.state })
+            }
+            None => seed.deserialize(Missing),
+        };
+        self.state.track(&path, res)
+    }
+
+    fn next_value<V: Deserialize<'a>>(&mut self) -> Result<V, Error> {
+        let (path, value) = self.value.take().expect("next_value called before next_key");
+        self.state.deserialize(value, &path)
+    }
+
+    fn next_entry<K: Deserialize<'a>, V: Deserialize<'a>>(
+        &mut self,
+    ) -> Result<Option<(K, V)>, Error> {
+        while let Some((key, path, value)) = self.next_entry_value() {
+            let entry = K::deserialize(Key(key))
+                .and_then(|key| Ok((key, self.state.deserialize(value, &path)?)));
+            match entry {
+                Ok(entry) => return Ok(Some(entry)),
+                Err(_) => self.state.discard(path),
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The elements of a JSON array. An invalid element is dropped in place
+struct LenientSeq<'a> {
+    elements: Enumerate<slice::Iter<'a, Value>>,
+    path: String,
+    state: &'a State,
+}
+impl<'a> SeqAccess<'a> for LenientSeq<'a> {
+    type Error = Error;
+
+    fn next_element_seed<S: DeserializeSeed<'a>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, Error> {
+        for (i, value) in self.elements.by_ref() {
+            let path = format!("{}[{i}]", self.path);
+            if self.state.skips(&path) {
+                continue;
+            }
+            let res = seed.deserialize(Lenient { value, path: path.clone(), state: self.state });
+            return self.state.track(&path, res).map(Some);
+        }
+        Ok(None)
+    }
+
+    fn next_element<T: Deserialize<'a>>(&mut self) -> Result<Option<T>, Error> {
+        for (i, value) in self.elements.by_ref() {
+            let path = format!("{}[{i}]", self.path);
+            if self.state.skips(&path) {
+                continue;
+            }
+            match self.state.deserialize(Some(value), &path) {
+                Ok(element) => return Ok(Some(element)),
+                Err(_) => self.state.discard(path),
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Stands in for a missing required field: empty for collections, no value otherwise
+struct Missing;
+
+impl<'de> Deserializer<'de> for Missing {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        Value::Null.deserialize_any(visitor).map_err(Error::Json)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_none()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        Value::Array(Vec::new()).deserialize_seq(visitor).map_err(Error::Json)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        Value::Object(Map::new()).deserialize_map(visitor).map_err(Error::Json)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct tuple tuple_struct enum identifier ignored_any
+    }
+}
+
+/// An object key. Numeric keys can be read as integers, e.g. the zooms of `wmbounds`
+struct Key<'a>(&'a str);
+
+macro_rules! parse_key {
+    ($($method:ident => $visit:ident)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.0.parse() {
+                Ok(n) => visitor.$visit(n),
+                Err(_) => visitor.visit_borrowed_str(self.0),
+            }
+        }
+    )*};
+}
+
+impl<'de> Deserializer<'de> for Key<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    parse_key! {
+        deserialize_i8 => visit_i8 deserialize_i16 => visit_i16 deserialize_i32 => visit_i32
+        deserialize_i64 => visit_i64 deserialize_u8 => visit_u8 deserialize_u16 => visit_u16
+        deserialize_u32 => visit_u32 deserialize_u64 => visit_u64
+    }
+
+    forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf option unit unit_struct
+        newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Attributions, BBox, Center, DrawType, Face, LayerMetaData, Scheme, TileStatsMetadata,
+        VectorLayer,
+    };
+    use alloc::{collections::BTreeMap, string::ToString, vec};
+
+    #[test]
+    fn test_parse_lenient_valid() {
+        let meta_str = serde_json::to_string(&Metadata {
+            name: "valid".into(),
+            faces: vec![Face::Face0, Face::Face2],
+            layers: BTreeMap::from([(
+                "water".into(),
+                LayerMetaData {
+                    minzoom: 0,
+                    maxzoom: 5,
+                    draw_types: vec![DrawType::Lines],
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        })
+        .unwrap();
+        let (meta, discarded) = Metadata::parse_lenient(&meta_str).unwrap();
+        assert_eq!(discarded, Vec::<String>::new());
+        assert_eq!(meta, serde_json::from_str::<Metadata>(&meta_str).unwrap());
+    }
+
+    #[test]
+    fn test_parse_lenient_invalid_values() {
+        let meta_str = r#"{
+            "s2tilejson": "1.0.0",
+            "version": "2",
+            "name": "lenient",
+            "scheme": "xyz",
+            "minzoom": "abc",
+            "maxzoom": 42,
+            "fillzoom": 3,
+            "faces": [0, 7, 2],
+            "bounds": [-180, -85, 180, 85, 0],
+            "centerpoint": { "lon": 1, "lat": 2, "zoom": 3 },
+            "wmbounds": { "0": [0, 0, 0, 0], "1": [0, 0] },
+            "s2bounds": { "1": { "2": [0, 0, 3, 3] }, "9": {} },
+            "attributions": { "OSM": "https://osm.org", "bad": 1 },
+            "layers": {
+                "water": { "minzoom": 0, "maxzoom": 5, "draw_types": [2, 42], "shape": 5 },
+                "broken": { "minzoom": "x", "maxzoom": 5 }
+            },
+            "vector_layers": [{ "id": "water", "fields": {} }, { "fields": {} }],
+            "tiles": "not an array",
+            "interval": null,
+            "generator": "tippecanoe"
+        }"#;
+        let (meta, discarded) = Metadata::parse_lenient(meta_str).unwrap();
+        assert_eq!(
+            discarded,
+            vec![
+                "$.attributions[\"bad\"]".to_string(),
+                "$.bounds".into(),
+                "$.faces[1]".into(),
+                "$.layers[\"broken\"]".into(),
+                "$.layers[\"water\"].draw_types[1]".into(),
+                "$.layers[\"water\"].shape".into(),
+                "$.maxzoom".into(),
+                "$.minzoom".into(),
+                "$.s2bounds[\"9\"]".into(),
+                "$.tiles".into(),
+                "$.vector_layers[1]".into(),
+                "$.version".into(),
+                "$.wmbounds[\"1\"]".into(),
+            ]
+        );
+
+        let mut expected = Metadata {
+            name: "lenient".into(),
+            scheme: Scheme::Xyz,
+            fillzoom: Some(3),
+            faces: vec![Face::Face0, Face::Face2],
+            centerpoint: Center { lon: 1., lat: 2., zoom: 3 },
+            wmbounds: BTreeMap::from([(0, BBox::new(0, 0, 0, 0))]),
+            attributions: Attributions::from([("OSM".into(), "https://osm.org".into())]),
+            layers: BTreeMap::from([(
+                "water".into(),
+                LayerMetaData {
+                    minzoom: 0,
+                    maxzoom: 5,
+                    draw_types: vec![DrawType::Lines],
+                    ..Default::default()
+                },
+            )]),
+            vector_layers: vec![VectorLayer { id: "water".into(), ..Default::default() }],
+            ..Default::default()
+        };
+        expected.s2bounds.get_mut(Face::Face1).insert(2, BBox::new(0, 0, 3, 3));
+        expected.set_extra("generator", &"tippecanoe");
+        assert_eq!(meta, expected);
+
+        // the strict parser refuses the whole document
+        assert!(serde_json::from_str::<Metadata>(meta_str).is_err());
+    }
+
+    #[test]
+    fn test_parse_lenient_nested() {
+        let meta_str = r#"{
+            "s2tilejson": "1.0.0",
+            "fillzoom": 31,
+            "tilejson": "three",
+            "tilestats": { "total": 3, "0": "x", "1": 3, "tiles": 1 },
+            "wmbounds": { "31": [0, 0, 0, 0], "2": [0, 0, 1, 1] },
+            "layers": { "water": { "minzoom": 0, "maxzoom": 5, "mShape": [] } }
+        }"#;
+        let (meta, discarded) = Metadata::parse_lenient(meta_str).unwrap();
+        assert_eq!(
+            discarded,
+            vec![
+                "$.fillzoom".to_string(),
+                "$.layers[\"water\"].mShape".into(),
+                "$.tilejson".into(),
+                "$.tilestats.tiles".into(),
+                "$.tilestats[\"0\"]".into(),
+                "$.wmbounds[\"31\"]".into(),
+            ]
+        );
+        // one bad count keeps the rest of the tilestats
+        assert_eq!(
+            meta.tilestats,
+            TileStatsMetadata { total: 3, total_1: 3, ..Default::default() }
+        );
+        assert_eq!(meta.wmbounds, BTreeMap::from([(2, BBox::new(0, 0, 1, 1))]));
+        assert_eq!((meta.fillzoom, meta.tilejson), (None, None));
+        assert_eq!(meta.layers["water"], LayerMetaData { maxzoom: 5, ..Default::default() });
+    }
+
+    #[test]
+    fn test_parse_lenient_tippecanoe_tilestats() {
+        let meta_str = r#"{
+            "s2tilejson": "1.0.0",
+            "tilestats": {
+                "layerCount": 2,
+                "layers": [{ "layer": "roads", "count": "x" }, { "count": 1 }]
+            }
+        }"#;
+        let (meta, discarded) = Metadata::parse_lenient(meta_str).unwrap();
+        assert_eq!(
+            discarded,
+            vec!["$.tilestats.layers[0].count".to_string(), "$.tilestats.layers[1]".into()]
+        );
+        assert_eq!(meta.tilestats, TileStatsMetadata::default());
+        let stats = meta.tippecanoe_tilestats.unwrap();
+        assert_eq!(stats.layers.len(), 1);
+        assert_eq!((stats.layers[0].layer.as_str(), stats.layers[0].count), ("roads", 0));
+    }
+
+    #[test]
+    fn test_parse_lenient_fills_required() {
+        let meta_str = r#"{
+            "s2tilejson": "1.0.0",
+            "s2bounds": { "2": { "1": [0, 0, 1, 1] } },
+            "layers": {
+                "water": { "minzoom": 0, "maxzoom": 5 },
+                "roads": { "minzoom": 0, "maxzoom": 5, "draw_types": 1, "shape": [] }
+            }
+        }"#;
+        // the strict parser requires every face and the layer draw types and shape
+        assert!(serde_json::from_str::<Metadata>(meta_str).is_err());
+
+        let (meta, discarded) = Metadata::parse_lenient(meta_str).unwrap();
+        assert_eq!(
+            discarded,
+            vec!["$.layers[\"roads\"].draw_types".to_string(), "$.layers[\"roads\"].shape".into(),]
+        );
+        let layer = LayerMetaData { maxzoom: 5, ..Default::default() };
+        assert_eq!(
+            meta.layers,
+            BTreeMap::from([("roads".into(), layer.clone()), ("water".into(), layer)])
+        );
+        let mut s2bounds = crate::FaceBounds::default();
+        s2bounds.get_mut(Face::Face2).insert(1, BBox::new(0, 0, 1, 1));
+        assert_eq!(meta.s2bounds, s2bounds);
+    }
+
+    #[test]
+    fn test_parse_lenient_required() {
+        let err = Metadata::parse_lenient(r#"{ "name": "no version" }"#).unwrap_err();
+        assert_eq!(err.to_string(), "required key $.s2tilejson is missing or invalid");
+        let err = Metadata::parse_lenient(r#"{ "s2tilejson": 1 }"#).unwrap_err();
+        assert!(matches!(err, LenientParseError::InvalidRequired(_)));
+        let err = Metadata::parse_lenient(r#"{ "s2tilejson": "one" }"#).unwrap_err();
+        assert!(matches!(err, LenientParseError::InvalidRequired(_)));
+        let err = Metadata::parse_lenient("[]").unwrap_err();
+        assert!(matches!(err, LenientParseError::NotAnObject));
+        assert_eq!(err.to_string(), "metadata must be a JSON object");
+        let err = Metadata::parse_lenient("{").unwrap_err();
+        assert!(matches!(err, LenientParseError::Json(_)));
+    }
+}
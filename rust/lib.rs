@@ -52,12 +52,29 @@
 //! ### Tools
 //!
 //! - [`crate::MetadataBuilder`]: Build a Metadata from scratch. Helper tool when constructing a set of tiles
+//! - [`crate::MetadataBuilder::add_feature`]: Infer layer shapes, draw types and zooms from the features actually written
+//! - [`crate::Metadata::to_mapbox`]: Convert a Metadata back into a Mapbox TileJSON 3.0.0 object
+//! - [`crate::Metadata::from_mbtiles_rows`] and [`crate::Metadata::to_mbtiles_rows`]: Convert between Metadata and the `name`/`value` rows of an MBTiles `metadata` table
+//! - [`crate::Metadata::from_pmtiles`] and [`crate::Metadata::to_pmtiles_header`]: Convert between Metadata and a [`crate::PMTilesHeader`], which parses and serializes the 127 byte PMTiles v3 header
+//! - [`crate::Metadata::to_ogc_tileset`]: Describe a Web Mercator tileset as OGC API - Tiles tileset metadata
+//! - [`crate::Metadata::merge`]: Stitch multiple Metadata into one, reporting any [`crate::MergeConflict`]
+//! - [`crate::Metadata::parse_lenient`]: Parse a Metadata, discarding invalid values instead of failing
+//! - [`crate::Metadata::has_tile_wm`] and [`crate::Metadata::has_tile_s2`]: Check if a tile exists before requesting it
+//! - [`crate::Metadata::resolve_tile`]: Find the tile holding the data of an overzoomed request
+//! - [`crate::Metadata::fill_tile_bounds`]: Derive `wmbounds` or `s2bounds` from the lon-lat `bounds`
+//! - [`crate::Metadata::tile_url`]: Expand the `tiles` URL templates for a [`crate::TileRequest`]
+//! - [`crate::Metadata::validate`]: Check a Metadata against the spec. Returns a list of [`crate::ValidationIssue`]
+//! - [`crate::parse_attribution_html`] and [`crate::attributions_to_html`]: Convert between legacy HTML `attribution` strings and [`crate::Attributions`]
+//! - [`crate::shape_union`]: Union two layer shapes, widening numbers and reporting a [`crate::ShapeConflict`]
+//! - [`crate::shape_to_fields`] and [`crate::fields_to_shape`]: Convert between layer shapes and TileJSON `fields`
 //!
 //! ### Top Level Types
 //!
 //! - [`crate::Metadata`]: Represents a TileJSON metadata object for the new S2 spec.
 //! - [`crate::MapboxTileJSONMetadata`]: Represents a TileJSON metadata object for the old Mapbox spec.
 //! - [`crate::UnknownMetadata`]: If we don't know which spec we are reading, we can treat the input as either.
+//! - [`crate::DetectedSpec`]: Which format and version [`crate::UnknownMetadata`] recognised
+//! - [`crate::TileJSONVersion`]: The legacy TileJSON versions 1.0.0 through 3.0.0 and their defaults
 //!
 //! ### Sub Types
 //!
@@ -66,6 +83,7 @@
 //! - [`crate::DrawType`]: Description of what kind of data is in the tile
 //! - [`crate::LayerMetaData`]: Each layer has metadata associated with it. Defined as blueprints pre-construction of vector data.
 //! - [`crate::TileStatsMetadata`]: Tilestats is simply a tracker to see where most of the tiles live
+//! - [`crate::TippecanoeTileStats`]: Layer and attribute stats written by tippecanoe, kept apart from the S2 tilestats
 //! - [`crate::Attributions`]: Attribution data is stored in an object. The key is the name of the attribution, and the value is the link
 //! - [`crate::FaceBounds`]: Track the S2 tile bounds of each face and zoom
 //! - [`crate::WMBounds`]: Track the WM tile bounds of each zoom `[zoom: number]: BBox`
@@ -74,9 +92,51 @@
 //! - [`crate::VectorLayer`]: Old spec tracks basic vector data
 //! - [`crate::Scheme`]: Default S2 tile scheme is fzxy Default Web Mercator tile scheme is xyz Adding a t prefix to the scheme will change the request to be time sensitive TMS is an oudated version that is not supported by s2maps-gpu
 //! - [`crate::Center`]: Store where the center of the data lives
+//! - [`crate::TileCoverage`]: Sparse index of exactly which tiles exist
+//! - [`crate::DetailedTileStats`]: Tile, feature and byte counts per zoom and layer
+//! - [`crate::ExtraMetadata`]: Unknown top level key-value pairs that are preserved on parse
 
 extern crate alloc;
 
+/// HTML attribution parsing and rendering
+pub mod attribution;
+/// Tile existence queries
+pub mod coverage;
+/// Spec detection of unknown metadata
+pub mod detect;
+/// Layer metadata inferred from streamed features
+pub mod feature;
+/// Lenient parsing that treats invalid values as absent
+pub mod lenient;
+/// Conversion from Metadata back to the Mapbox TileJSON spec
+pub mod mapbox;
+/// MBTiles metadata table rows
+pub mod mbtiles;
+/// Merging of multiple Metadata into one
+pub mod merge;
+/// OGC API - Tiles tileset metadata
+pub mod ogc;
+/// Overzoom and fillzoom tile resolution
+pub mod overzoom;
+/// PMTiles v3 header
+pub mod pmtiles;
+/// Web Mercator and S2 projection math
+mod projection;
+/// Shape union and compatibility checking
+pub mod shape;
+/// Per-layer and per-zoom tile statistics
+pub mod stats;
+/// Tile bounds derived from lon-lat bounds
+pub mod tile_bounds;
+/// Tile URL template expansion
+pub mod tile_url;
+/// Version-aware defaults of the legacy TileJSON spec
+pub mod tilejson;
+/// Typed tippecanoe tilestats
+pub mod tippecanoe;
+/// Spec validation of a Metadata object
+pub mod validate;
+
 use alloc::{
     borrow::ToOwned,
     boxed::Box,
@@ -85,8 +145,26 @@ use alloc::{
     string::String,
     vec::Vec,
 };
+pub use attribution::*;
+use core::{fmt, str::FromStr};
+pub use coverage::*;
+pub use detect::*;
+pub use lenient::*;
+pub use mapbox::*;
+pub use mbtiles::*;
+pub use merge::*;
+pub use ogc::*;
+pub use overzoom::*;
+pub use pmtiles::*;
+use projection::{s2_tile_lon_lat_bounds, wm_tile_lon_lat_bounds};
 pub use s2json::*;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::DeserializeOwned};
+pub use shape::*;
+pub use stats::*;
+pub use tile_url::*;
+pub use tilejson::*;
+pub use tippecanoe::*;
+pub use validate::*;
 
 /// Use bounds as floating point numbers for longitude and latitude
 pub type LonLatBounds = BBox<f64>;
@@ -293,6 +371,10 @@ impl TileStatsMetadata {
     }
 }
 
+/// All six faces of the S2 cube in order
+pub(crate) const FACES: [Face; 6] =
+    [Face::Face0, Face::Face1, Face::Face2, Face::Face3, Face::Face4, Face::Face5];
+
 /// Track the S2 tile bounds of each face and zoom
 #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
 pub struct FaceBounds {
@@ -346,6 +428,10 @@ impl FaceBounds {
 /// `[zoom: number]: BBox`
 pub type WMBounds = BTreeMap<u8, TileBounds>;
 
+/// Unknown key-value pairs found at the top level of a metadata object.
+/// The spec requires they be exposed so users can optionally handle them.
+pub type ExtraMetadata = BTreeMap<String, serde_json::Value>;
+
 /// Check the source type of the layer
 #[derive(Serialize, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "lowercase")]
@@ -484,17 +570,54 @@ pub enum Scheme {
     /// The TMS scheme
     Tms,
 }
-impl From<&str> for Scheme {
-    fn from(scheme: &str) -> Self {
+impl TryFrom<&str> for Scheme {
+    type Error = UnknownSchemeError;
+    fn try_from(scheme: &str) -> Result<Self, Self::Error> {
         match scheme {
-            "fzxy" => Scheme::Fzxy,
-            "tfzxy" => Scheme::Tfzxy,
-            "xyz" => Scheme::Xyz,
-            "txyz" => Scheme::Txyz,
-            _ => Scheme::Tms,
+            "fzxy" => Ok(Scheme::Fzxy),
+            "tfzxy" => Ok(Scheme::Tfzxy),
+            "xyz" => Ok(Scheme::Xyz),
+            "txyz" => Ok(Scheme::Txyz),
+            "tms" => Ok(Scheme::Tms),
+            _ => Err(UnknownSchemeError(scheme.into())),
         }
     }
 }
+impl FromStr for Scheme {
+    type Err = UnknownSchemeError;
+    fn from_str(scheme: &str) -> Result<Self, Self::Err> {
+        Scheme::try_from(scheme)
+    }
+}
+impl Scheme {
+    /// Returns true if the scheme describes S2 tiles (`fzxy` or `tfzxy`)
+    pub fn is_s2(&self) -> bool {
+        matches!(self, Scheme::Fzxy | Scheme::Tfzxy)
+    }
+
+    /// The projection of the tiles, [`Projection::S2`] for `fzxy` and `tfzxy` and
+    /// [`Projection::WG`] (Web Mercator) otherwise
+    pub fn projection(&self) -> Projection {
+        if self.is_s2() { Projection::S2 } else { Projection::WG }
+    }
+
+    /// Sniff the scheme from the placeholders of `tiles` URL templates. `{face}` describes S2
+    /// tiles and `{t}` time sensitive tiles. Returns `None` if neither is present
+    pub fn from_tiles(tiles: &[String]) -> Option<Scheme> {
+        let has = |placeholder: &str| tiles.iter().any(|url| url.contains(placeholder));
+        match (has("{face}"), has("{t}")) {
+            (true, true) => Some(Scheme::Tfzxy),
+            (true, false) => Some(Scheme::Fzxy),
+            (false, true) => Some(Scheme::Txyz),
+            (false, false) => None,
+        }
+    }
+
+    /// Returns true if the scheme is time sensitive (`tfzxy` or `txyz`)
+    pub fn is_temporal(&self) -> bool {
+        matches!(self, Scheme::Tfzxy | Scheme::Txyz)
+    }
+}
 impl From<Scheme> for &str {
     fn from(scheme: Scheme) -> Self {
         match scheme {
@@ -507,6 +630,15 @@ impl From<Scheme> for &str {
     }
 }
 
+/// A scheme string that is not one of `fzxy`, `tfzxy`, `xyz`, `txyz` or `tms`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownSchemeError(pub String);
+impl fmt::Display for UnknownSchemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown scheme \"{}\", expected fzxy, tfzxy, xyz, txyz or tms", self.0)
+    }
+}
+
 /// Store where the center of the data lives
 #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
 pub struct Center {
@@ -532,7 +664,7 @@ pub struct Center {
 /// ## Links
 /// [S2TileJSON Spec](https://github.com/Open-S2/s2-tilejson/tree/master/s2-tilejson-spec/1.0.0)
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
-#[serde(default)]
+#[serde(default, remote = "Self")]
 pub struct Metadata {
     /// The version of the s2-tilejson spec. Matches the pattern: `^\d+\.\d+\.\d+\w?[\w\d]*$`.
     pub s2tilejson: String,
@@ -570,6 +702,7 @@ pub struct Metadata {
     /// Track layer metadata
     pub layers: LayersMetaData,
     /// Track tile stats for each face and total overall
+    #[serde(skip_deserializing)] // read by the Deserialize impl, see `split_tilestats`
     pub tilestats: TileStatsMetadata,
     /// Old spec but required for functional compatibility, track basic layer metadata
     pub vector_layers: Vec<VectorLayer>,
@@ -604,7 +737,53 @@ pub struct Metadata {
     /// Template for interactivity.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub template: Option<String>,
+
+    // Extensions
+    /// Sparse index of exactly which tiles exist. Falls back to `wmbounds`/`s2bounds` when absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<TileCoverage>,
+    /// Tile, feature and byte counts per zoom and layer. Kept apart from `tilestats`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detailed_tilestats: Option<DetailedTileStats>,
+    /// Layer and attribute stats written by tippecanoe. Kept apart from `tilestats`, they are
+    /// read from the `tilestats` key when it has tippecanoe's layout
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tippecanoe_tilestats: Option<TippecanoeTileStats>,
+
+    /// Any unknown keys (e.g. tippecanoe's `generator`) are preserved here
+    #[serde(flatten)]
+    pub extra: ExtraMetadata,
+}
+impl Serialize for Metadata {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Metadata::serialize(self, serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Metadata {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut meta = Metadata::deserialize(deserializer)?;
+        if let Some(tilestats) = meta.extra.remove("tilestats") {
+            split_tilestats(&mut meta, tilestats).map_err(serde::de::Error::custom)?;
+        }
+        Ok(meta)
+    }
 }
+
+/// The `tilestats` key holds either the S2 tile counts or, in metadata written by tippecanoe,
+/// its layer stats. The latter go to `tippecanoe_tilestats`, leaving the S2 counts empty
+pub(crate) fn split_tilestats(
+    meta: &mut Metadata,
+    tilestats: serde_json::Value,
+) -> Result<(), serde_json::Error> {
+    if is_tippecanoe_tilestats(&tilestats) {
+        let stats = TippecanoeTileStats::deserialize(tilestats)?;
+        meta.tippecanoe_tilestats.get_or_insert(stats);
+    } else {
+        meta.tilestats = TileStatsMetadata::deserialize(tilestats)?;
+    }
+    Ok(())
+}
+
 impl Default for Metadata {
     fn default() -> Self {
         Self {
@@ -637,9 +816,29 @@ impl Default for Metadata {
             template: None,
             tilejson: None,
             tiles: None,
+            coverage: None,
+            detailed_tilestats: None,
+            tippecanoe_tilestats: None,
+            extra: ExtraMetadata::new(),
         }
     }
 }
+impl Metadata {
+    /// Read an unknown key as type `T`. Returns `None` if missing or of the wrong type
+    pub fn get_extra<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        get_extra(&self.extra, key)
+    }
+
+    /// Store a value under an unknown key, replacing any previous value
+    pub fn set_extra<T: Serialize>(&mut self, key: &str, value: &T) {
+        set_extra(&mut self.extra, key, value);
+    }
+
+    /// Remove an unknown key, returning its raw JSON value if present
+    pub fn remove_extra(&mut self, key: &str) -> Option<serde_json::Value> {
+        self.extra.remove(key)
+    }
+}
 
 /// # TileJSON V3.0.0
 ///
@@ -716,27 +915,67 @@ pub struct MapboxTileJSONMetadata {
     /// Encoding of the tileset.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub encoding: Option<Encoding>,
+    /// Any unknown keys are preserved here
+    #[serde(flatten)]
+    pub extra: ExtraMetadata,
 }
 impl MapboxTileJSONMetadata {
+    /// Read an unknown key as type `T`. Returns `None` if missing or of the wrong type
+    pub fn get_extra<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        get_extra(&self.extra, key)
+    }
+
+    /// Store a value under an unknown key, replacing any previous value
+    pub fn set_extra<T: Serialize>(&mut self, key: &str, value: &T) {
+        set_extra(&mut self.extra, key, value);
+    }
+
+    /// Remove an unknown key, returning its raw JSON value if present
+    pub fn remove_extra(&mut self, key: &str) -> Option<serde_json::Value> {
+        self.extra.remove(key)
+    }
+
     /// Converts a MapboxTileJSONMetadata to a Metadata
+    ///
+    /// Missing values fall back to the defaults of the [`TileJSONVersion`] in `tilejson`, e.g. a
+    /// 2.2.0 raster manifest defaults to a `maxzoom` of 22. Without a valid `tilejson` the S2
+    /// defaults are used. The scheme is inferred by [`MapboxTileJSONMetadata::infer_scheme`] and
+    /// the tile bounds are filled from explicit lon-lat bounds by [`Metadata::fill_tile_bounds`].
     pub fn to_metadata(&self) -> Metadata {
+        let spec = self.spec_version();
         let [lon, lat, zoom] = self.center.unwrap_or([0.0, 0.0, 0.0]);
-        Metadata {
+        let default_maxzoom = spec.map_or(27, |spec| spec.default_maxzoom());
+        let (minzoom, maxzoom) =
+            (self.minzoom.unwrap_or(0), self.maxzoom.unwrap_or(default_maxzoom));
+        let (r#type, extension) = self.source_type_and_extension();
+        let scheme = self.infer_scheme();
+        let mut layers = vector_layers_to_layers(&self.vector_layers, minzoom, maxzoom);
+        let mut extra = self.extra.clone();
+        let tippecanoe = self.get_extra::<TippecanoeTileStats>("tilestats");
+        if let Some(stats) = &tippecanoe {
+            refine_layers(&mut layers, stats, minzoom, maxzoom);
+            extra.remove("tilestats");
+        }
+        let mut metadata = Metadata {
             s2tilejson: "1.0.0".into(),
             version: self.version.clone().unwrap_or("1.0.0".into()),
             name: self.name.clone().unwrap_or("default".into()),
-            scheme: self.scheme.unwrap_or_default(),
+            scheme,
             description: self.description.clone().unwrap_or("Built with s2maps-cli".into()),
-            r#type: self.r#type.unwrap_or_default(),
-            extension: self.extension.clone().unwrap_or("pbf".into()),
-            faces: Vec::from([Face::Face0]),
-            bounds: self.bounds.unwrap_or_default(),
-            minzoom: self.minzoom.unwrap_or(0),
-            maxzoom: self.maxzoom.unwrap_or(27),
+            r#type,
+            extension,
+            faces: if scheme.is_s2() { FACES.into() } else { Vec::from([Face::Face0]) },
+            bounds: self.bounds.or(spec.map(|spec| spec.default_bounds())).unwrap_or_default(),
+            minzoom,
+            maxzoom,
             centerpoint: Center { lon, lat, zoom: zoom as u8 },
             center: Some([lon, lat, zoom]),
-            attributions: extract_link_info(self.attribution.as_ref().unwrap_or(&"".into()))
+            attributions: self
+                .attribution
+                .as_deref()
+                .map(parse_attribution_html)
                 .unwrap_or_default(),
+            layers,
             vector_layers: self.vector_layers.clone(),
             encoding: self.encoding.unwrap_or(Encoding::None),
             attribution: self.attribution.clone(),
@@ -746,19 +985,32 @@ impl MapboxTileJSONMetadata {
             legend: self.legend.clone(),
             template: self.template.clone(),
             fillzoom: self.fillzoom,
+            tippecanoe_tilestats: tippecanoe,
+            extra,
             ..Default::default()
+        };
+        if self.bounds.is_some() {
+            metadata.fill_tile_bounds();
         }
+        metadata
     }
 }
 
-/// If we don't know which spec we are reading, we can treat the input as either
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// If we don't know which spec we are reading, we can treat the input as either.
+///
+/// The spec is detected from the keys of the input: `s2tilejson` for the new spec, `tilejson`
+/// or tippecanoe's layout for the old spec, and string values like `"bounds": "-180,-85,180,85"`
+/// for MBTiles metadata rows. Anything else fails to deserialize. See
+/// [`UnknownMetadata::detected_spec`].
+#[derive(Serialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum UnknownMetadata {
     /// New spec
     Metadata(Box<Metadata>),
     /// Old spec
     Mapbox(Box<MapboxTileJSONMetadata>),
+    /// MBTiles metadata rows, read into the old spec
+    MBTiles(Box<MapboxTileJSONMetadata>),
 }
 impl UnknownMetadata {
     /// Converts a UnknownMetadata to a Metadata
@@ -766,6 +1018,7 @@ impl UnknownMetadata {
         match self {
             UnknownMetadata::Metadata(m) => *m.clone(),
             UnknownMetadata::Mapbox(m) => m.to_metadata(),
+            UnknownMetadata::MBTiles(m) => mbtiles_to_metadata(m),
         }
     }
 }
@@ -787,8 +1040,15 @@ impl UnknownMetadata {
 /// - [`MetadataBuilder::set_encoding`]: Set the encoding of the data. [default=none]
 /// - [`MetadataBuilder::add_attribution`]: Add an attribution to the data
 /// - [`MetadataBuilder::add_layer`]: Add a layer to the data
+/// - [`MetadataBuilder::add_feature`]: Infer the layer metadata from a feature written to a tile
+/// - [`MetadataBuilder::enable_coverage`]: Record a sparse [`TileCoverage`] index of every tile added
+/// - [`MetadataBuilder::enable_detailed_stats`]: Gather [`DetailedTileStats`] via [`MetadataBuilder::add_tile_size`], [`MetadataBuilder::add_layer_size`] and [`MetadataBuilder::add_layer_features`]
+/// - [`MetadataBuilder::absorb`]: Merge an already built [`Metadata`] into the builder
+/// - [`MetadataBuilder::fork`]: Fork a lightweight partial builder, e.g. one per worker thread
+/// - [`MetadataBuilder::merge`]: Combine partial builders in any order, reporting any conflicting layers
 /// - [`MetadataBuilder::add_tile_wm`]: Add the WM tile metadata
 /// - [`MetadataBuilder::add_tile_s2`]: Add the S2 tile metadata
+/// - [`MetadataBuilder::add_tile_wm_auto`] and [`MetadataBuilder::add_tile_s2_auto`]: Add the tile metadata, computing the lon-lat bounds of the tile
 /// - [`MetadataBuilder::update_center`]: Update the center now that all tiles have been added
 /// - [`MetadataBuilder::add_bounds_wm`]: Add the bounds of the tile for WM data
 /// - [`MetadataBuilder::add_bounds_s2`]: Add the bounds of the tile for S2 data
@@ -798,6 +1058,9 @@ pub struct MetadataBuilder {
     lon_lat_bounds: LonLatBounds,
     faces: BTreeSet<Face>,
     metadata: Metadata,
+    coverage: Option<TileCoverage>,
+    coverage_cap: usize,
+    degraded_zooms: BTreeSet<CoverageZoom>,
 }
 impl Default for MetadataBuilder {
     fn default() -> Self {
@@ -810,6 +1073,9 @@ impl Default for MetadataBuilder {
             },
             faces: BTreeSet::new(),
             metadata: Metadata { minzoom: 30, maxzoom: 0, ..Metadata::default() },
+            coverage: None,
+            coverage_cap: 0,
+            degraded_zooms: BTreeSet::new(),
         }
     }
 }
@@ -821,9 +1087,20 @@ impl MetadataBuilder {
         // set the bounds
         self.metadata.bounds = self.lon_lat_bounds;
         // set the faces
-        for face in &self.faces {
-            self.metadata.faces.push(*face);
+        self.metadata.faces = self.faces.iter().copied().collect();
+        // give every layer a vector layer, ordered like the layers so merged builders agree
+        for (name, layer) in &self.metadata.layers {
+            if !self.metadata.vector_layers.iter().any(|l| &l.id == name) {
+                self.metadata.vector_layers.push(to_vector_layer(name, layer));
+            }
         }
+        self.metadata.vector_layers.sort_by(|a, b| a.id.cmp(&b.id));
+        // set the coverage, degrading the largest zooms until it fits the cap
+        self.metadata.coverage = self.coverage.clone().map(|mut coverage| {
+            coverage.cap(self.coverage_cap);
+            coverage
+        });
+        self.metadata.coverage = self.metadata.coverage.take().filter(|c| !c.is_empty());
         // return the result
         self.metadata.to_owned()
     }
@@ -863,23 +1140,45 @@ impl MetadataBuilder {
         self.metadata.encoding = encoding;
     }
 
+    /// Record a sparse [`TileCoverage`] index of every tile added from now on.
+    ///
+    /// `max_runs` caps the size of the committed index. While it is exceeded, the zoom with the
+    /// most runs is dropped and falls back to the rectangle form of `wmbounds`/`s2bounds`. The
+    /// cap is applied on commit so the result only depends on which tiles were added, not their
+    /// order.
+    pub fn enable_coverage(&mut self, max_runs: usize) {
+        self.coverage.get_or_insert_default();
+        self.coverage_cap = max_runs;
+    }
+
     /// add an attribution
     pub fn add_attribution(&mut self, display_name: &str, href: &str) {
         self.metadata.attributions.insert(display_name.into(), href.into());
     }
 
-    /// Add the layer metadata
-    pub fn add_layer(&mut self, name: &str, layer: &LayerMetaData) {
-        // Only insert if the key does not exist
-        if self.metadata.layers.entry(name.into()).or_insert(layer.clone()).eq(&layer) {
-            // Also add to vector_layers only if the key was not present and the insert was successful
-            self.metadata.vector_layers.push(VectorLayer {
-                id: name.into(), // No need to clone again; we use the moved value
-                description: layer.description.clone(),
-                minzoom: Some(layer.minzoom),
-                maxzoom: Some(layer.maxzoom),
-                fields: BTreeMap::new(),
-            });
+    /// Add the layer metadata.
+    ///
+    /// If the layer already exists, the two are unioned with [`LayerMetaData::union`] so that new
+    /// properties, draw types and zooms are not lost. Returns a [`ShapeConflict`] and keeps the
+    /// existing layer if their shapes are irreconcilable.
+    pub fn add_layer(&mut self, name: &str, layer: &LayerMetaData) -> Result<(), ShapeConflict> {
+        match self.metadata.layers.get_mut(name) {
+            Some(existing) => {
+                existing.union(layer)?;
+                // keep the vector layer in sync
+                if let Some(vector_layer) =
+                    self.metadata.vector_layers.iter_mut().find(|l| l.id == name)
+                {
+                    vector_layer.description = existing.description.clone();
+                    vector_layer.minzoom = Some(existing.minzoom);
+                    vector_layer.maxzoom = Some(existing.maxzoom);
+                    vector_layer.fields = shape_to_fields(&existing.shape);
+                }
+            }
+            None => {
+                self.metadata.layers.insert(name.into(), layer.clone());
+                self.metadata.vector_layers.push(to_vector_layer(name, layer));
+            }
         }
         // update minzoom and maxzoom
         if layer.minzoom < self.metadata.minzoom {
@@ -888,6 +1187,7 @@ impl MetadataBuilder {
         if layer.maxzoom > self.metadata.maxzoom {
             self.metadata.maxzoom = layer.maxzoom;
         }
+        Ok(())
     }
 
     /// Add the WM tile metadata
@@ -895,6 +1195,7 @@ impl MetadataBuilder {
         self.metadata.tilestats.total += 1;
         self.faces.insert(Face::Face0);
         self.add_bounds_wm(zoom, x, y);
+        self.add_coverage(None, zoom, x, y);
         self.update_lon_lat_bounds(ll_bounds);
     }
 
@@ -903,9 +1204,28 @@ impl MetadataBuilder {
         self.metadata.tilestats.increment(face);
         self.faces.insert(face);
         self.add_bounds_s2(face, zoom, x, y);
+        self.add_coverage(Some(face), zoom, x, y);
         self.update_lon_lat_bounds(ll_bounds);
     }
 
+    /// Add the WM tile metadata, computing the lon-lat bounds of the tile. `y` is counted from the
+    /// south if the scheme is `tms` and from the north otherwise
+    pub fn add_tile_wm_auto(&mut self, zoom: u8, x: u32, y: u32) {
+        let y_north = match self.metadata.scheme {
+            Scheme::Tms => ((1u64 << zoom) - 1).saturating_sub(y as u64),
+            _ => y as u64,
+        };
+        let ll_bounds = wm_tile_lon_lat_bounds(zoom, x as u64, y_north);
+        self.add_tile_wm(zoom, x, y, &ll_bounds);
+    }
+
+    /// Add the S2 tile metadata, computing the lon-lat bounds of the tile. Tiles touching a pole
+    /// or crossing the antimeridian span every longitude
+    pub fn add_tile_s2_auto(&mut self, face: Face, zoom: u8, x: u32, y: u32) {
+        let ll_bounds = s2_tile_lon_lat_bounds(face, zoom, x as u64, y as u64);
+        self.add_tile_s2(face, zoom, x, y, &ll_bounds);
+    }
+
     /// Update the center now that all tiles have been added
     fn update_center(&mut self) {
         let Metadata { minzoom, maxzoom, .. } = self.metadata;
@@ -949,6 +1269,18 @@ impl MetadataBuilder {
         bbox.top = bbox.top.max(y);
     }
 
+    /// Add the tile to the coverage index if enabled and the zoom has not been degraded
+    fn add_coverage(&mut self, face: Option<Face>, zoom: u8, x: u32, y: u32) {
+        let Some(coverage) = &mut self.coverage else { return };
+        if self.degraded_zooms.contains(&(face, zoom)) {
+            return;
+        }
+        match face {
+            None => coverage.insert_wm(zoom, x as u64, y as u64),
+            Some(face) => coverage.insert_s2(face, zoom, x as u64, y as u64),
+        }
+    }
+
     /// Update the lon-lat bounds so eventually we can find the center point of the data
     fn update_lon_lat_bounds(&mut self, ll_bounds: &LonLatBounds) {
         self.lon_lat_bounds.left = ll_bounds.left.min(self.lon_lat_bounds.left);
@@ -958,30 +1290,31 @@ impl MetadataBuilder {
     }
 }
 
-/// Extract the link info from the HTML
-fn extract_link_info(html_string: &str) -> Option<Attributions> {
-    // Find the start and end of the 'href' attribute
-    let href_start = html_string.find("href='")?;
-    let href_value_start = href_start + "href='".len();
-    let href_end = html_string[href_value_start..].find("'")?;
-    let href_value = &html_string[href_value_start..href_value_start + href_end];
-
-    // Find the start and end of the link text
-    let text_start = html_string.find(">")?;
-    let text_value_start = text_start + 1;
-    let text_end = html_string.find("</a>")?;
-    let text_value = &html_string[text_value_start..text_end];
+/// The legacy vector layer describing a layer
+fn to_vector_layer(name: &str, layer: &LayerMetaData) -> VectorLayer {
+    VectorLayer {
+        id: name.into(),
+        description: layer.description.clone(),
+        minzoom: Some(layer.minzoom),
+        maxzoom: Some(layer.maxzoom),
+        fields: shape_to_fields(&layer.shape),
+    }
+}
 
-    let mut map = Attributions::new();
-    map.insert(text_value.into(), href_value.into());
+/// Read an unknown key as type `T`
+fn get_extra<T: DeserializeOwned>(extra: &ExtraMetadata, key: &str) -> Option<T> {
+    T::deserialize(extra.get(key)?).ok()
+}
 
-    Some(map)
+/// Store a value under an unknown key. Values that fail to serialize are stored as `null`
+fn set_extra<T: Serialize>(extra: &mut ExtraMetadata, key: &str, value: &T) {
+    extra.insert(key.into(), serde_json::to_value(value).unwrap_or(serde_json::Value::Null));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloc::vec;
+    use alloc::{string::ToString, vec};
     use s2json::{PrimitiveShape, ShapeType};
 
     #[test]
@@ -992,7 +1325,7 @@ mod tests {
         meta_builder.set_name("OSM".into());
         meta_builder.set_description("A free editable map of the whole world.".into());
         meta_builder.set_version("1.0.0".into());
-        meta_builder.set_scheme("fzxy".into()); // 'fzxy' | 'tfzxy' | 'xyz' | 'txyz' | 'tms'
+        meta_builder.set_scheme(Scheme::Fzxy); // 'fzxy' | 'tfzxy' | 'xyz' | 'txyz' | 'tms'
         meta_builder.set_type("vector".into()); // 'vector' | 'json' | 'raster' | 'raster-dem' | 'grid' | 'markers'
         meta_builder.set_encoding("none".into()); // 'gz' | 'br' | 'none'
         meta_builder.set_extension("pbf".into());
@@ -1018,7 +1351,7 @@ mod tests {
             shape: shape.clone(),
             m_shape: None,
         };
-        meta_builder.add_layer("water_lines", &layer);
+        meta_builder.add_layer("water_lines", &layer).unwrap();
 
         // as you build tiles, add the tiles metadata:
         // WM:
@@ -1046,7 +1379,7 @@ mod tests {
                 name: "OSM".into(),
                 description: "A free editable map of the whole world.".into(),
                 version: "1.0.0".into(),
-                scheme: "fzxy".into(),
+                scheme: Scheme::Fzxy,
                 r#type: "vector".into(),
                 encoding: "none".into(),
                 extension: "pbf".into(),
@@ -1111,7 +1444,12 @@ mod tests {
                     description: Some("water_lines".into()),
                     minzoom: Some(0),
                     maxzoom: Some(13),
-                    fields: BTreeMap::new()
+                    fields: BTreeMap::from([
+                        ("class".into(), "String".into()),
+                        ("info.name".into(), "String".into()),
+                        ("info.value".into(), "Number".into()),
+                        ("offset".into(), "Number".into()),
+                    ])
                 }]),
                 ..Default::default()
             }
@@ -1121,7 +1459,7 @@ mod tests {
 
         assert_eq!(
             meta_str,
-            "{\"s2tilejson\":\"1.0.0\",\"version\":\"1.0.0\",\"name\":\"OSM\",\"scheme\":\"fzxy\",\"description\":\"A free editable map of the whole world.\",\"type\":\"vector\",\"extension\":\"pbf\",\"encoding\":\"none\",\"faces\":[0,1],\"bounds\":[-120.0,-20.0,44.0,72.0],\"wmbounds\":{\"0\":[0,0,0,0]},\"s2bounds\":{\"0\":{},\"1\":{\"5\":[22,37,22,37]},\"2\":{},\"3\":{},\"4\":{},\"5\":{}},\"minzoom\":0,\"maxzoom\":13,\"centerpoint\":{\"lon\":-38.0,\"lat\":26.0,\"zoom\":6},\"attributions\":{\"OpenStreetMap\":\"https://www.openstreetmap.org/copyright/\"},\"layers\":{\"water_lines\":{\"description\":\"water_lines\",\"minzoom\":0,\"maxzoom\":13,\"draw_types\":[2],\"shape\":{\"class\":\"string\",\"info\":{\"name\":\"string\",\"value\":\"i64\"},\"offset\":\"f64\"}}},\"tilestats\":{\"total\":2,\"0\":0,\"1\":1,\"2\":0,\"3\":0,\"4\":0,\"5\":0},\"vector_layers\":[{\"id\":\"water_lines\",\"description\":\"water_lines\",\"minzoom\":0,\"maxzoom\":13,\"fields\":{}}],\"tilejson\":null}"
+            "{\"s2tilejson\":\"1.0.0\",\"version\":\"1.0.0\",\"name\":\"OSM\",\"scheme\":\"fzxy\",\"description\":\"A free editable map of the whole world.\",\"type\":\"vector\",\"extension\":\"pbf\",\"encoding\":\"none\",\"faces\":[0,1],\"bounds\":[-120.0,-20.0,44.0,72.0],\"wmbounds\":{\"0\":[0,0,0,0]},\"s2bounds\":{\"0\":{},\"1\":{\"5\":[22,37,22,37]},\"2\":{},\"3\":{},\"4\":{},\"5\":{}},\"minzoom\":0,\"maxzoom\":13,\"centerpoint\":{\"lon\":-38.0,\"lat\":26.0,\"zoom\":6},\"attributions\":{\"OpenStreetMap\":\"https://www.openstreetmap.org/copyright/\"},\"layers\":{\"water_lines\":{\"description\":\"water_lines\",\"minzoom\":0,\"maxzoom\":13,\"draw_types\":[2],\"shape\":{\"class\":\"string\",\"info\":{\"name\":\"string\",\"value\":\"i64\"},\"offset\":\"f64\"}}},\"tilestats\":{\"total\":2,\"0\":0,\"1\":1,\"2\":0,\"3\":0,\"4\":0,\"5\":0},\"vector_layers\":[{\"id\":\"water_lines\",\"description\":\"water_lines\",\"minzoom\":0,\"maxzoom\":13,\"fields\":{\"class\":\"String\",\"info.name\":\"String\",\"info.value\":\"Number\",\"offset\":\"Number\"}}],\"tilejson\":null}"
         );
 
         let meta_reparsed: Metadata =
@@ -1439,11 +1777,30 @@ mod tests {
     #[test]
     fn test_scheme() {
         // from string
-        assert_eq!(Scheme::from("fzxy"), Scheme::Fzxy);
-        assert_eq!(Scheme::from("tfzxy"), Scheme::Tfzxy);
-        assert_eq!(Scheme::from("xyz"), Scheme::Xyz);
-        assert_eq!(Scheme::from("txyz"), Scheme::Txyz);
-        assert_eq!(Scheme::from("tms"), Scheme::Tms);
+        assert_eq!(Scheme::try_from("fzxy"), Ok(Scheme::Fzxy));
+        assert_eq!(Scheme::try_from("tfzxy"), Ok(Scheme::Tfzxy));
+        assert_eq!(Scheme::try_from("xyz"), Ok(Scheme::Xyz));
+        assert_eq!(Scheme::try_from("txyz"), Ok(Scheme::Txyz));
+        assert_eq!("tms".parse(), Ok(Scheme::Tms));
+        let err = Scheme::try_from("zxy").unwrap_err();
+        assert_eq!(err, UnknownSchemeError("zxy".into()));
+        assert_eq!(
+            err.to_string(),
+            "unknown scheme \"zxy\", expected fzxy, tfzxy, xyz, txyz or tms"
+        );
+
+        // projection
+        assert_eq!(Scheme::Fzxy.projection(), Projection::S2);
+        assert_eq!(Scheme::Tfzxy.projection(), Projection::S2);
+        assert_eq!(Scheme::Xyz.projection(), Projection::WG);
+        assert_eq!(Scheme::Tms.projection(), Projection::WG);
+
+        // sniffed from tiles
+        let tiles = |url: &str| vec![String::from(url)];
+        assert_eq!(Scheme::from_tiles(&tiles("/{face}/{z}/{x}/{y}.pbf")), Some(Scheme::Fzxy));
+        assert_eq!(Scheme::from_tiles(&tiles("/{t}/{face}/{z}/{x}/{y}")), Some(Scheme::Tfzxy));
+        assert_eq!(Scheme::from_tiles(&tiles("/{t}/{z}/{x}/{y}.png")), Some(Scheme::Txyz));
+        assert_eq!(Scheme::from_tiles(&tiles("/{z}/{x}/{y}.png")), None);
 
         // to string
         assert_eq!(core::convert::Into::<&str>::into(Scheme::Fzxy), "fzxy");
@@ -1485,8 +1842,56 @@ mod tests {
             }
         }"#;
 
-        let _meta: Metadata =
+        let mut meta: Metadata =
             serde_json::from_str(meta_str).unwrap_or_else(|e| panic!("ERROR: {e}"));
+        // tippecanoe's tilestats are typed instead of discarded
+        let stats = meta.tippecanoe_tilestats.clone().unwrap();
+        assert_eq!(stats.layer_count, 1);
+        assert_eq!(stats.layers[0].layer, "test_fixture_1pmtiles");
+        assert_eq!(stats.layers[0].geometry, TippecanoeGeometry::Polygon);
+        assert_eq!(meta.tilestats, TileStatsMetadata::default());
+        assert_eq!(meta.get_extra::<TippecanoeTileStats>("tilestats"), None);
+        assert_eq!(meta.get_extra::<String>("generator"), Some("tippecanoe v2.5.0".into()));
+        assert_eq!(
+            meta.get_extra::<String>("generator_options"),
+            Some("./tippecanoe -zg -o test_fixture_1.pmtiles --force".into())
+        );
+        assert_eq!(meta.get_extra::<u64>("generator"), None);
+        assert_eq!(meta.get_extra::<String>("missing"), None);
+
+        // round trip keeps the unknown keys
+        let json = serde_json::to_string(&meta).unwrap();
+        let meta2: Metadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(meta, meta2);
+        let unknown: UnknownMetadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(unknown.to_metadata().extra, meta.extra);
+
+        meta.set_extra("generator", &2);
+        assert_eq!(meta.get_extra::<u8>("generator"), Some(2));
+        assert_eq!(meta.remove_extra("generator"), Some(2.into()));
+        assert_eq!(meta.get_extra::<u8>("generator"), None);
+
+        let mut mapbox: MapboxTileJSONMetadata = serde_json::from_str(meta_str).unwrap();
+        mapbox.set_extra("vendor", &vec![1, 2]);
+        assert_eq!(mapbox.get_extra::<Vec<u8>>("vendor"), Some(vec![1, 2]));
+        assert_eq!(mapbox.remove_extra("vendor"), Some(serde_json::json!([1, 2])));
+        let converted = mapbox.to_metadata();
+        assert_eq!(converted.get_extra::<String>("generator"), Some("tippecanoe v2.5.0".into()));
+
+        assert_eq!(converted.tippecanoe_tilestats, Some(stats.clone()));
+        assert_eq!(converted.get_extra::<TippecanoeTileStats>("tilestats"), None);
+        assert_eq!(
+            converted.layers["test_fixture_1pmtiles"],
+            LayerMetaData {
+                description: Some("".into()),
+                minzoom: 0,
+                maxzoom: 0,
+                draw_types: vec![DrawType::Polygons],
+                ..Default::default()
+            }
+        );
+        let (back, _) = converted.to_mapbox();
+        assert_eq!(back.get_extra::<TippecanoeTileStats>("tilestats"), Some(stats));
     }
 
     #[test]
@@ -1537,76 +1942,96 @@ mod tests {
         let meta_mapbox: MapboxTileJSONMetadata =
             serde_json::from_str(meta_str).unwrap_or_else(|e| panic!("ERROR: {e}"));
         let meta_new = meta_mapbox.to_metadata();
-        assert_eq!(
-            meta_new,
-            Metadata {
-                name: "OpenStreetMap".into(),
-                description: "A free editable map of the whole world.".into(),
-                version: "1.0.0".into(),
-                scheme: Scheme::Xyz,
-                r#type: "vector".into(),
-                encoding: Encoding::None, // Changed from "none".into() to None
-                extension: "pbf".into(),
-                attributions: Attributions::from([(
-                    "OSM contributors".into(),
-                    "https://openstreetmap.org".into()
-                )]),
-                bounds: BBox::new(-180., -85., 180., 85.),
-                vector_layers: meta_mapbox.vector_layers.clone(),
-                maxzoom: 18,
-                minzoom: 0,
-                centerpoint: Center { lat: 0.0, lon: 0.0, zoom: 0 },
-                wmbounds: WMBounds::default(),
-                faces: vec![Face::Face0],
-                s2bounds: FaceBounds::default(),
-                tilestats: TileStatsMetadata::default(),
-                layers: LayersMetaData::default(),
-                s2tilejson: "1.0.0".into(),
-                attribution: Some(
-                    "<a href='https://openstreetmap.org'>OSM contributors</a>".into()
+        let mut expected = Metadata {
+            name: "OpenStreetMap".into(),
+            description: "A free editable map of the whole world.".into(),
+            version: "1.0.0".into(),
+            scheme: Scheme::Xyz,
+            r#type: "vector".into(),
+            encoding: Encoding::None, // Changed from "none".into() to None
+            extension: "pbf".into(),
+            attributions: Attributions::from([(
+                "OSM contributors".into(),
+                "https://openstreetmap.org".into(),
+            )]),
+            bounds: BBox::new(-180., -85., 180., 85.),
+            vector_layers: meta_mapbox.vector_layers.clone(),
+            maxzoom: 18,
+            minzoom: 0,
+            centerpoint: Center { lat: 0.0, lon: 0.0, zoom: 0 },
+            wmbounds: WMBounds::default(),
+            faces: vec![Face::Face0],
+            s2bounds: FaceBounds::default(),
+            tilestats: TileStatsMetadata::default(),
+            layers: LayersMetaData::from([
+                (
+                    "telephone".into(),
+                    LayerMetaData {
+                        minzoom: 0,
+                        maxzoom: 18,
+                        shape: Shape::from([
+                            ("payment".into(), ShapeType::Primitive(PrimitiveShape::String)),
+                            ("phone_number".into(), ShapeType::Primitive(PrimitiveShape::String)),
+                        ]),
+                        ..Default::default()
+                    },
                 ),
-                tiles: Some(meta_mapbox.tiles.clone()),
-                fillzoom: meta_mapbox.fillzoom,
-                center: Some([0.0, 0.0, 0.0]),
-                ..Default::default()
-            },
-        );
+                (
+                    "bicycle_parking".into(),
+                    LayerMetaData {
+                        minzoom: 0,
+                        maxzoom: 18,
+                        shape: Shape::from([
+                            ("type".into(), ShapeType::Primitive(PrimitiveShape::String)),
+                            ("year_installed".into(), ShapeType::Primitive(PrimitiveShape::String)),
+                        ]),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "showers".into(),
+                    LayerMetaData {
+                        minzoom: 0,
+                        maxzoom: 18,
+                        shape: Shape::from([
+                            (
+                                "water_temperature".into(),
+                                ShapeType::Primitive(PrimitiveShape::String),
+                            ),
+                            ("wear_sandles".into(), ShapeType::Primitive(PrimitiveShape::String)),
+                            ("wheelchair".into(), ShapeType::Primitive(PrimitiveShape::String)),
+                        ]),
+                        ..Default::default()
+                    },
+                ),
+            ]),
+            s2tilejson: "1.0.0".into(),
+            attribution: Some("<a href='https://openstreetmap.org'>OSM contributors</a>".into()),
+            tiles: Some(meta_mapbox.tiles.clone()),
+            fillzoom: meta_mapbox.fillzoom,
+            center: Some([0.0, 0.0, 0.0]),
+            extra: ExtraMetadata::from([(
+                "something_custom".into(),
+                "this is my unique field".into(),
+            )]),
+            ..Default::default()
+        };
+        // the tile bounds are derived from the lon-lat bounds
+        expected.fill_tile_bounds();
+        assert_eq!(meta_new, expected);
+        assert_eq!(meta_new.wmbounds[&1], BBox::new(0, 0, 1, 1));
+        assert_eq!(meta_new.wmbounds[&10], BBox::new(0, 1, 1023, 1022));
 
         let meta_mapbox_from_unknown: UnknownMetadata =
             serde_json::from_str(meta_str).unwrap_or_else(|e| panic!("ERROR: {e}"));
-        let meta_new = meta_mapbox_from_unknown.to_metadata();
         assert_eq!(
-            meta_new,
-            Metadata {
-                name: "OpenStreetMap".into(),
-                description: "A free editable map of the whole world.".into(),
-                version: "1.0.0".into(),
-                scheme: Scheme::Xyz,
-                r#type: "vector".into(),
-                encoding: Encoding::None, // Changed from "none".into() to None
-                extension: "pbf".into(),
-                attributions: Attributions::default(),
-                bounds: BBox::new(-180., -85., 180., 85.),
-                vector_layers: meta_mapbox.vector_layers.clone(),
-                maxzoom: 18,
-                minzoom: 0,
-                centerpoint: Center { lat: 0.0, lon: 0.0, zoom: 0 },
-                wmbounds: WMBounds::default(),
-                faces: vec![],
-                s2bounds: FaceBounds::default(),
-                tilestats: TileStatsMetadata::default(),
-                layers: LayersMetaData::default(),
-                s2tilejson: "1.0.0".into(),
-                attribution: Some(
-                    "<a href='https://openstreetmap.org'>OSM contributors</a>".into()
-                ),
-                tiles: Some(meta_mapbox.tiles.clone()),
-                fillzoom: meta_mapbox.fillzoom,
-                center: None,
-                tilejson: Some("3.0.0".into()),
-                ..Default::default()
-            },
+            meta_mapbox_from_unknown.detected_spec(),
+            DetectedSpec::TileJSON("3.0.0".into())
         );
+        let meta_new = meta_mapbox_from_unknown.to_metadata();
+        assert_eq!(meta_new, meta_mapbox.to_metadata());
+        assert_eq!(meta_new.faces, vec![Face::Face0]);
+        assert_eq!(meta_new.layers.len(), meta_mapbox.vector_layers.len());
     }
 
     #[test]
@@ -1668,14 +2093,44 @@ mod tests {
                     total_2: 0,
                     total_3: 0,
                     total_4: 0,
-                    total_5: 0
+                    total_5: 0,
                 },
                 vector_layers: vec![],
+                extra: ExtraMetadata::from([("format".into(), "zxy".into())]),
                 ..Default::default()
             }
         );
     }
 
+    #[test]
+    fn test_builder_auto_bounds() {
+        let mut meta_builder = MetadataBuilder::default();
+        meta_builder.set_scheme(Scheme::Xyz);
+        meta_builder.add_tile_wm_auto(1, 1, 0);
+        let meta = meta_builder.commit();
+        assert_eq!(meta.wmbounds[&1], BBox::new(1, 0, 1, 0));
+        assert_eq!((meta.bounds.left, meta.bounds.bottom, meta.bounds.right), (0., 0., 180.));
+        assert!((meta.bounds.top - 85.0511287798066).abs() < 1e-9);
+
+        // tms counts the rows from the south
+        let mut meta_builder = MetadataBuilder::default();
+        meta_builder.set_scheme(Scheme::Tms);
+        meta_builder.add_tile_wm_auto(1, 1, 0);
+        let meta = meta_builder.commit();
+        assert!((meta.bounds.bottom + 85.0511287798066).abs() < 1e-9 && meta.bounds.top < 1e-9);
+
+        // the tiles around the north pole span every longitude
+        let mut meta_builder = MetadataBuilder::default();
+        meta_builder.add_tile_s2_auto(Face::Face2, 1, 0, 0);
+        meta_builder.add_tile_s2_auto(Face::Face2, 1, 1, 1);
+        let meta = meta_builder.commit();
+        assert_eq!(meta.faces, vec![Face::Face2]);
+        assert_eq!(meta.tilestats.get(Face::Face2), 2);
+        assert_eq!((meta.bounds.left, meta.bounds.right, meta.bounds.top), (-180., 180., 90.));
+        assert!((meta.bounds.bottom - 35.264389682754654).abs() < 1e-9);
+        assert_eq!(meta.centerpoint.lon, 0.);
+    }
+
     #[test]
     fn test_faces() {
         let meta = Metadata {
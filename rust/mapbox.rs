@@ -0,0 +1,506 @@
+use crate::{
+    FACES, Face, LayerMetaData, LayersMetaData, MapboxTileJSONMetadata, Metadata, PrimitiveShape,
+    PrimitiveShapeType, Scheme, Shape, ShapePrimitive, ShapeType, VectorLayer,
+    attributions_to_html, set_extra,
+};
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+impl Metadata {
+    /// Converts a Metadata to a Mapbox TileJSON 3.0.0 object.
+    ///
+    /// Returns the converted metadata alongside the JSON paths of every field that could not be
+    /// represented in the old spec (e.g. `$.faces`, `$.s2bounds`, `$.layers["water"].mShape`).
+    pub fn to_mapbox(&self) -> (MapboxTileJSONMetadata, Vec<String>) {
+        let mut lost = Vec::new();
+
+        // S2 only data
+        if self.scheme.is_s2() || self.faces.iter().any(|f| *f != Face::Face0) {
+            lost.push("$.faces".into());
+        }
+        if FACES.iter().any(|f| !self.s2bounds.get(*f).is_empty()) {
+            lost.push("$.s2bounds".into());
+        }
+        if !self.wmbounds.is_empty() {
+            lost.push("$.wmbounds".into());
+        }
+        if self.coverage.is_some() {
+            lost.push("$.coverage".into());
+        }
+        if self.detailed_tilestats.is_some() {
+            lost.push("$.detailed_tilestats".into());
+        }
+        if self.tilestats.total > 0 {
+            lost.push("$.tilestats".into());
+        }
+        if self.interval.is_some() {
+            lost.push("$.interval".into());
+        }
+        let scheme = match self.scheme {
+            Scheme::Xyz | Scheme::Tms => Some(self.scheme),
+            Scheme::Txyz => {
+                lost.push("$.scheme".into());
+                Some(Scheme::Xyz)
+            }
+            Scheme::Fzxy | Scheme::Tfzxy => {
+                lost.push("$.scheme".into());
+                None
+            }
+        };
+        for (name, layer) in &self.layers {
+            if !layer.draw_types.is_empty() {
+                lost.push(format!("$.layers[\"{name}\"].draw_types"));
+            }
+            if layer.m_shape.is_some() {
+                lost.push(format!("$.layers[\"{name}\"].mShape"));
+            }
+        }
+
+        let attribution = if self.attributions.is_empty() {
+            self.attribution.clone()
+        } else {
+            Some(attributions_to_html(&self.attributions))
+        };
+
+        let mut extra = self.extra.clone();
+        if let Some(stats) = &self.tippecanoe_tilestats {
+            set_extra(&mut extra, "tilestats", stats);
+        }
+
+        let meta = MapboxTileJSONMetadata {
+            tilejson: "3.0.0".into(),
+            tiles: self.tiles.clone().unwrap_or_default(),
+            vector_layers: merge_vector_layers(&self.vector_layers, &self.layers),
+            attribution,
+            bounds: Some(self.bounds),
+            center: Some([
+                self.centerpoint.lon,
+                self.centerpoint.lat,
+                self.centerpoint.zoom as f64,
+            ]),
+            data: self.data.clone(),
+            description: Some(self.description.clone()),
+            fillzoom: self.fillzoom,
+            grids: self.grids.clone(),
+            legend: self.legend.clone(),
+            maxzoom: Some(self.maxzoom),
+            minzoom: Some(self.minzoom),
+            name: Some(self.name.clone()),
+            scheme,
+            template: self.template.clone(),
+            version: Some(self.version.clone()),
+            r#type: Some(self.r#type),
+            extension: Some(self.extension.clone()),
+            encoding: Some(self.encoding),
+            extra,
+        };
+
+        (meta, lost)
+    }
+}
+
+/// Flatten a layer [`Shape`] into TileJSON `fields`.
+///
+/// Primitives become `String`, `Number`, `Boolean` or `Null`, nested objects become dotted keys
+/// and arrays are flagged as `Array<TYPE>`.
+pub fn shape_to_fields(shape: &Shape) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    flatten_shape(&mut fields, "", shape);
+    fields
+}
+
+fn flatten_shape(fields: &mut BTreeMap<String, String>, prefix: &str, shape: &Shape) {
+    for (key, value) in shape.iter() {
+        let key = format!("{prefix}{key}");
+        match value {
+            ShapeType::Primitive(prim) => {
+                fields.insert(key, primitive_field(prim).into());
+            }
+            ShapeType::Nested(nested) => flatten_shape(fields, &format!("{key}."), nested),
+            ShapeType::Array(arr) => match arr.first() {
+                Some(PrimitiveShapeType::NestedPrimitive(nested)) => {
+                    for (sub_key, prim) in nested.iter() {
+                        let sub_key = format!("{key}.{sub_key}");
+                        fields.insert(sub_key, format!("Array<{}>", primitive_field(prim)));
+                    }
+                }
+                Some(PrimitiveShapeType::Primitive(prim)) => {
+                    fields.insert(key, format!("Array<{}>", primitive_field(prim)));
+                }
+                None => {
+                    fields.insert(key, "Array".to_string());
+                }
+            },
+        }
+    }
+}
+
+fn primitive_field(prim: &PrimitiveShape) -> &'static str {
+    match prim {
+        PrimitiveShape::String => "String",
+        PrimitiveShape::U64 | PrimitiveShape::I64 | PrimitiveShape::F32 | PrimitiveShape::F64 => {
+            "Number"
+        }
+        PrimitiveShape::Bool => "Boolean",
+        PrimitiveShape::Null => "Null",
+    }
+}
+
+/// Infer a best-effort [`Shape`] from TileJSON `fields`. The inverse of [`shape_to_fields`].
+///
+/// `String`, `Number`, `Boolean` and `Null` map to `string`, `f64`, `bool` and `null`. Dotted
+/// keys become nested objects, or an array of objects when flagged as `Array<TYPE>`. Anything
+/// else (usually a human readable description of the field) is assumed to be a `string`.
+pub fn fields_to_shape(fields: &BTreeMap<String, String>) -> Shape {
+    let mut shape = Shape::new();
+    for (key, value) in fields {
+        let path: Vec<&str> = key.split('.').collect();
+        let array_type = value
+            .strip_prefix("Array")
+            .map(|t| t.strip_prefix('<').and_then(|t| t.strip_suffix('>')).map(field_primitive));
+        match array_type {
+            // array of objects
+            Some(Some(prim)) if path.len() > 1 => {
+                let (last, parent) = path.split_last().unwrap();
+                let entry = shape_entry(&mut shape, parent);
+                match entry {
+                    ShapeType::Array(arr) => match arr.first_mut() {
+                        Some(PrimitiveShapeType::NestedPrimitive(nested)) => {
+                            nested.insert((*last).into(), prim);
+                        }
+                        _ => *arr = vec_of_nested(last, prim),
+                    },
+                    _ => *entry = ShapeType::Array(vec_of_nested(last, prim)),
+                }
+            }
+            Some(Some(prim)) => {
+                *shape_entry(&mut shape, &path) =
+                    ShapeType::Array(Vec::from([PrimitiveShapeType::Primitive(prim)]))
+            }
+            Some(None) if value == "Array" => {
+                *shape_entry(&mut shape, &path) = ShapeType::Array(Vec::new())
+            }
+            _ => *shape_entry(&mut shape, &path) = ShapeType::Primitive(field_primitive(value)),
+        }
+    }
+    shape
+}
+
+/// Find or create the shape entry at a dotted path, replacing anything that is in the way
+fn shape_entry<'a>(shape: &'a mut Shape, path: &[&str]) -> &'a mut ShapeType {
+    let (first, rest) = path.split_first().expect("paths are never empty");
+    let entry =
+        shape.entry((*first).into()).or_insert_with(|| ShapeType::Primitive(PrimitiveShape::Null));
+    if rest.is_empty() {
+        return entry;
+    }
+    if !matches!(entry, ShapeType::Nested(_)) {
+        *entry = ShapeType::Nested(Shape::new());
+    }
+    match entry {
+        ShapeType::Nested(nested) => shape_entry(nested, rest),
+        _ => unreachable!(),
+    }
+}
+
+fn vec_of_nested(key: &str, prim: PrimitiveShape) -> Vec<PrimitiveShapeType> {
+    Vec::from([PrimitiveShapeType::NestedPrimitive(ShapePrimitive::from([(key.into(), prim)]))])
+}
+
+fn field_primitive(field: &str) -> PrimitiveShape {
+    match field {
+        "Number" => PrimitiveShape::F64,
+        "Boolean" => PrimitiveShape::Bool,
+        "Null" => PrimitiveShape::Null,
+        _ => PrimitiveShape::String,
+    }
+}
+
+/// Build the layers of a Metadata from legacy vector layers, inferring each shape from the
+/// layer fields. Missing zooms fall back to the zoom range of the whole tileset.
+pub(crate) fn vector_layers_to_layers(
+    vector_layers: &[VectorLayer],
+    minzoom: u8,
+    maxzoom: u8,
+) -> LayersMetaData {
+    vector_layers
+        .iter()
+        .map(|layer| {
+            let meta = LayerMetaData {
+                description: layer.description.clone(),
+                minzoom: layer.minzoom.unwrap_or(minzoom),
+                maxzoom: layer.maxzoom.unwrap_or(maxzoom),
+                shape: fields_to_shape(&layer.fields),
+                ..Default::default()
+            };
+            (layer.id.clone(), meta)
+        })
+        .collect()
+}
+
+/// Keep the existing vector layers, fill in empty fields from the layer shapes and add any
+/// layers that are missing
+fn merge_vector_layers(vector_layers: &[VectorLayer], layers: &LayersMetaData) -> Vec<VectorLayer> {
+    let mut res: Vec<VectorLayer> = vector_layers.to_vec();
+    for vector_layer in res.iter_mut() {
+        if let Some(layer) = layers.get(&vector_layer.id)
+            && vector_layer.fields.is_empty()
+        {
+            vector_layer.fields = shape_to_fields(&layer.shape);
+        }
+    }
+    for (name, layer) in layers {
+        if !res.iter().any(|l| &l.id == name) {
+            res.push(VectorLayer {
+                id: name.clone(),
+                description: layer.description.clone(),
+                minzoom: Some(layer.minzoom),
+                maxzoom: Some(layer.maxzoom),
+                fields: shape_to_fields(&layer.shape),
+            });
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Attributions, BBox, Center, DrawType, SourceType};
+    use alloc::vec;
+
+    #[test]
+    fn test_shape_to_fields() {
+        let shape: Shape = serde_json::from_str(
+            r#"{
+                "class": "string",
+                "offset": "f64",
+                "count": "u64",
+                "visible": "bool",
+                "info": { "name": "string", "value": "i64" },
+                "tags": ["string"],
+                "points": [{ "x": "f32", "label": "string" }]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            shape_to_fields(&shape),
+            BTreeMap::from([
+                ("class".into(), "String".into()),
+                ("count".into(), "Number".into()),
+                ("info.name".into(), "String".into()),
+                ("info.value".into(), "Number".into()),
+                ("offset".into(), "Number".into()),
+                ("points.label".into(), "Array<String>".into()),
+                ("points.x".into(), "Array<Number>".into()),
+                ("tags".into(), "Array<String>".into()),
+                ("visible".into(), "Boolean".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_fields_to_shape() {
+        let fields = BTreeMap::from([
+            ("class".into(), "String".into()),
+            ("count".into(), "Number".into()),
+            ("info.name".into(), "String".into()),
+            ("info.value".into(), "Number".into()),
+            ("points.label".into(), "Array<String>".into()),
+            ("points.x".into(), "Array<Number>".into()),
+            ("tags".into(), "Array<String>".into()),
+            ("visible".into(), "Boolean".into()),
+            ("nothing".into(), "Null".into()),
+            ("empty".into(), "Array".into()),
+            ("kind".into(), "the kind of road".into()),
+        ]);
+        let expected: Shape = serde_json::from_str(
+            r#"{
+                "class": "string",
+                "count": "f64",
+                "info": { "name": "string", "value": "f64" },
+                "points": [{ "label": "string", "x": "f64" }],
+                "tags": ["string"],
+                "visible": "bool",
+                "nothing": "null",
+                "empty": [],
+                "kind": "string"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(fields_to_shape(&fields), expected);
+        // round trips through the fields form
+        assert_eq!(shape_to_fields(&fields_to_shape(&fields)), {
+            let mut fields = fields.clone();
+            fields.insert("kind".into(), "String".into());
+            fields
+        });
+        // a primitive in the way of a nested key is replaced
+        let fields =
+            BTreeMap::from([("a".into(), "String".into()), ("a.b".into(), "Number".into())]);
+        let expected: Shape = serde_json::from_str(r#"{ "a": { "b": "f64" } }"#).unwrap();
+        assert_eq!(fields_to_shape(&fields), expected);
+    }
+
+    #[test]
+    fn test_vector_layers_to_layers() {
+        let vector_layers = vec![VectorLayer {
+            id: "water".into(),
+            description: Some("lakes and rivers".into()),
+            minzoom: Some(4),
+            maxzoom: None,
+            fields: BTreeMap::from([("depth".into(), "Number".into())]),
+        }];
+        assert_eq!(
+            vector_layers_to_layers(&vector_layers, 0, 14),
+            BTreeMap::from([(
+                "water".into(),
+                LayerMetaData {
+                    description: Some("lakes and rivers".into()),
+                    minzoom: 4,
+                    maxzoom: 14,
+                    shape: serde_json::from_str(r#"{ "depth": "f64" }"#).unwrap(),
+                    ..Default::default()
+                }
+            )])
+        );
+    }
+
+    #[test]
+    fn test_to_mapbox_wm() {
+        let meta = Metadata {
+            name: "OSM".into(),
+            scheme: Scheme::Xyz,
+            faces: vec![Face::Face0],
+            bounds: BBox::new(-10., -5., 10., 5.),
+            minzoom: 0,
+            maxzoom: 14,
+            centerpoint: Center { lon: 1., lat: 2., zoom: 7 },
+            attributions: Attributions::from([(
+                "OSM".into(),
+                "https://www.openstreetmap.org/copyright/".into(),
+            )]),
+            tiles: Some(vec!["https://a.tiles.org/{z}/{x}/{y}.pbf".into()]),
+            layers: BTreeMap::from([(
+                "water".into(),
+                LayerMetaData {
+                    minzoom: 0,
+                    maxzoom: 14,
+                    shape: serde_json::from_str(r#"{ "class": "string" }"#).unwrap(),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+        let (mapbox, lost) = meta.to_mapbox();
+        assert_eq!(lost, Vec::<String>::new());
+        assert_eq!(
+            mapbox,
+            MapboxTileJSONMetadata {
+                tilejson: "3.0.0".into(),
+                tiles: vec!["https://a.tiles.org/{z}/{x}/{y}.pbf".into()],
+                vector_layers: vec![VectorLayer {
+                    id: "water".into(),
+                    description: None,
+                    minzoom: Some(0),
+                    maxzoom: Some(14),
+                    fields: BTreeMap::from([("class".into(), "String".into())]),
+                }],
+                attribution: Some(
+                    "<a href=\"https://www.openstreetmap.org/copyright/\">OSM</a>".into()
+                ),
+                bounds: Some(BBox::new(-10., -5., 10., 5.)),
+                center: Some([1., 2., 7.]),
+                description: Some("Built with s2maps-cli".into()),
+                maxzoom: Some(14),
+                minzoom: Some(0),
+                name: Some("OSM".into()),
+                scheme: Some(Scheme::Xyz),
+                version: Some("1.0.0".into()),
+                r#type: Some(SourceType::Vector),
+                extension: Some("pbf".into()),
+                encoding: Some(crate::Encoding::None),
+                ..Default::default()
+            }
+        );
+
+        // and back again
+        let back = mapbox.to_metadata();
+        assert_eq!(back.name, meta.name);
+        assert_eq!(back.centerpoint, meta.centerpoint);
+        assert_eq!(back.attributions, meta.attributions);
+    }
+
+    #[test]
+    fn test_to_mapbox_s2_lossy() {
+        let mut meta = Metadata {
+            faces: vec![Face::Face0, Face::Face3],
+            attribution: Some("<a href='https://osm.org'>OSM</a>".into()),
+            interval: Some(1_000),
+            vector_layers: vec![VectorLayer {
+                id: "roads".into(),
+                fields: BTreeMap::from([("kind".into(), "road kind".into())]),
+                ..Default::default()
+            }],
+            layers: BTreeMap::from([
+                (
+                    "roads".into(),
+                    LayerMetaData {
+                        draw_types: vec![DrawType::Lines],
+                        shape: serde_json::from_str(r#"{ "kind": "string" }"#).unwrap(),
+                        m_shape: Some(serde_json::from_str(r#"{ "speed": "f64" }"#).unwrap()),
+                        ..Default::default()
+                    },
+                ),
+                ("empty".into(), LayerMetaData::default()),
+            ]),
+            ..Default::default()
+        };
+        meta.s2bounds.get_mut(Face::Face3).insert(0, BBox::new(0, 0, 0, 0));
+        meta.wmbounds.insert(0, BBox::new(0, 0, 0, 0));
+        meta.tilestats.increment(Face::Face3);
+
+        let (mapbox, lost) = meta.to_mapbox();
+        assert_eq!(
+            lost,
+            vec![
+                "$.faces".to_string(),
+                "$.s2bounds".into(),
+                "$.wmbounds".into(),
+                "$.tilestats".into(),
+                "$.interval".into(),
+                "$.scheme".into(),
+                "$.layers[\"roads\"].draw_types".into(),
+                "$.layers[\"roads\"].mShape".into(),
+            ]
+        );
+        assert_eq!(mapbox.scheme, None);
+        assert_eq!(mapbox.attribution, Some("<a href='https://osm.org'>OSM</a>".into()));
+        // existing fields are kept, missing layers are added
+        assert_eq!(
+            mapbox.vector_layers,
+            vec![
+                VectorLayer {
+                    id: "roads".into(),
+                    fields: BTreeMap::from([("kind".into(), "road kind".into())]),
+                    ..Default::default()
+                },
+                VectorLayer {
+                    id: "empty".into(),
+                    minzoom: Some(0),
+                    maxzoom: Some(0),
+                    ..Default::default()
+                },
+            ]
+        );
+
+        meta.scheme = Scheme::Txyz;
+        meta.faces = vec![Face::Face0];
+        let (mapbox, _) = meta.to_mapbox();
+        assert_eq!(mapbox.scheme, Some(Scheme::Xyz));
+    }
+}
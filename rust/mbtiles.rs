@@ -0,0 +1,291 @@
+use crate::{BBox, MapboxTileJSONMetadata, Metadata, Scheme, SourceType};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+use serde_json::{Map, Value};
+
+/// Reasons [`Metadata::from_mbtiles_rows`] refuses a metadata table
+#[derive(Debug)]
+pub enum MBTilesError {
+    /// A row has a value that can't be parsed, e.g. a `bounds` without four numbers
+    InvalidRow {
+        /// The name of the row
+        name: String,
+        /// The value of the row
+        value: String,
+    },
+    /// The `json` row is not a JSON object
+    InvalidJson(serde_json::Error),
+}
+impl fmt::Display for MBTilesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MBTilesError::InvalidRow { name, value } => {
+                write!(f, "invalid MBTiles metadata row {name}: \"{value}\"")
+            }
+            MBTilesError::InvalidJson(e) => write!(f, "invalid MBTiles json row: {e}"),
+        }
+    }
+}
+
+impl Metadata {
+    /// Build a Metadata from the `name`/`value` rows of an MBTiles `metadata` table.
+    ///
+    /// `format`, `bounds`, `center`, `minzoom`, `maxzoom`, `attribution` and the `vector_layers`
+    /// of the `json` row follow the [MBTiles 1.3 spec](https://github.com/mapbox/mbtiles-spec/blob/master/1.3/spec.md).
+    /// The MBTiles `type` (`overlay` or `baselayer`) is dropped as the source type is inferred from
+    /// `format`. Unknown rows (e.g. tippecanoe's `generator`) and unknown keys of the `json` row are
+    /// kept as extra metadata, so tippecanoe's `tilestats` are picked up as well.
+    pub fn from_mbtiles_rows<I, K, V>(rows: I) -> Result<Metadata, MBTilesError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        Ok(mbtiles_to_metadata(&mbtiles_rows_to_mapbox(rows)?))
+    }
+
+    /// Convert into the `name`/`value` rows of an MBTiles `metadata` table.
+    ///
+    /// The `vector_layers` and any tippecanoe `tilestats` are written to the `json` row. Extra
+    /// string values become their own rows while any other extra values are added to the `json`
+    /// row. Data the MBTiles spec can't represent is dropped, see [`Metadata::to_mapbox`].
+    pub fn to_mbtiles_rows(&self) -> Vec<(String, String)> {
+        let (mapbox, _) = self.to_mapbox();
+        let format = match self.r#type {
+            SourceType::Vector => "pbf".into(),
+            _ => self.extension.clone(),
+        };
+        let mut rows: Vec<(String, String)> = Vec::from([
+            ("name".into(), self.name.clone()),
+            ("format".into(), format),
+            ("type".into(), "overlay".into()),
+            ("version".into(), self.version.clone()),
+            ("description".into(), self.description.clone()),
+            ("minzoom".into(), self.minzoom.to_string()),
+            ("maxzoom".into(), self.maxzoom.to_string()),
+        ]);
+        if let Some(bounds) = mapbox.bounds {
+            let bounds =
+                format!("{},{},{},{}", bounds.left, bounds.bottom, bounds.right, bounds.top);
+            rows.push(("bounds".into(), bounds));
+        }
+        if let Some([lon, lat, zoom]) = mapbox.center {
+            rows.push(("center".into(), format!("{lon},{lat},{zoom}")));
+        }
+        if let Some(attribution) = mapbox.attribution {
+            rows.push(("attribution".into(), attribution));
+        }
+
+        let mut json = Map::new();
+        if !mapbox.vector_layers.is_empty() {
+            let vector_layers = serde_json::to_value(&mapbox.vector_layers).unwrap_or_default();
+            json.insert("vector_layers".into(), vector_layers);
+        }
+        for (key, value) in mapbox.extra {
+            match value {
+                Value::String(value) if key != "json" => rows.push((key, value)),
+                value => {
+                    json.insert(key, value);
+                }
+            }
+        }
+        if !json.is_empty() {
+            rows.push(("json".into(), Value::Object(json).to_string()));
+        }
+
+        rows
+    }
+}
+
+/// Read the rows of an MBTiles `metadata` table into legacy TileJSON
+pub(crate) fn mbtiles_rows_to_mapbox<I, K, V>(
+    rows: I,
+) -> Result<MapboxTileJSONMetadata, MBTilesError>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut mapbox = MapboxTileJSONMetadata {
+        tilejson: "3.0.0".into(),
+        scheme: Some(Scheme::Xyz),
+        ..Default::default()
+    };
+    for (name, value) in rows {
+        let (name, value) = (name.as_ref(), value.as_ref());
+        let invalid = || MBTilesError::InvalidRow { name: name.into(), value: value.into() };
+        match name {
+            "name" => mapbox.name = Some(value.into()),
+            "description" => mapbox.description = Some(value.into()),
+            "version" => mapbox.version = Some(value.into()),
+            "attribution" => mapbox.attribution = Some(value.into()),
+            "format" => {
+                let (source_type, extension) = match value {
+                    "pbf" | "mvt" => (SourceType::Vector, "pbf"),
+                    _ => (SourceType::Raster, value),
+                };
+                mapbox.r#type = Some(source_type);
+                mapbox.extension = Some(extension.into());
+            }
+            "bounds" => {
+                let [left, bottom, right, top] = parse_numbers(value).ok_or_else(invalid)?;
+                mapbox.bounds = Some(BBox::new(left, bottom, right, top));
+            }
+            "center" => {
+                mapbox.center = Some(match parse_numbers::<3>(value) {
+                    Some(center) => center,
+                    None => {
+                        let [lon, lat] = parse_numbers(value).ok_or_else(invalid)?;
+                        [lon, lat, 0.]
+                    }
+                });
+            }
+            "minzoom" => mapbox.minzoom = Some(value.trim().parse().map_err(|_| invalid())?),
+            "maxzoom" => mapbox.maxzoom = Some(value.trim().parse().map_err(|_| invalid())?),
+            "type" => {}
+            "json" => {
+                let json: Map<String, Value> =
+                    serde_json::from_str(value).map_err(MBTilesError::InvalidJson)?;
+                for (key, value) in json {
+                    if key == "vector_layers" {
+                        mapbox.vector_layers =
+                            serde_json::from_value(value).map_err(MBTilesError::InvalidJson)?;
+                    } else {
+                        mapbox.extra.insert(key, value);
+                    }
+                }
+            }
+            _ => {
+                mapbox.extra.insert(name.into(), Value::String(value.into()));
+            }
+        }
+    }
+
+    Ok(mapbox)
+}
+
+/// Convert MBTiles rows read by [`mbtiles_rows_to_mapbox`] into a Metadata
+pub(crate) fn mbtiles_to_metadata(mapbox: &MapboxTileJSONMetadata) -> Metadata {
+    Metadata { tiles: None, ..mapbox.to_metadata() }
+}
+
+/// Parse exactly `N` comma separated numbers
+fn parse_numbers<const N: usize>(value: &str) -> Option<[f64; N]> {
+    let mut res = [0.; N];
+    let mut parts = value.split(',');
+    for slot in res.iter_mut() {
+        *slot = parts.next()?.trim().parse().ok()?;
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrawType, Face, TippecanoeGeometry};
+    use alloc::vec;
+
+    fn rows() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("name", "roads"),
+            ("format", "pbf"),
+            ("type", "overlay"),
+            ("version", "2"),
+            ("description", "roads of the world"),
+            ("bounds", "-180.0,-85.05,180,85.05"),
+            ("center", "10.5,20,4"),
+            ("minzoom", "0"),
+            ("maxzoom", "14"),
+            ("attribution", "<a href=\"https://openstreetmap.org\">OSM</a>"),
+            ("generator", "tippecanoe v2.5.0"),
+            (
+                "json",
+                r#"{
+                    "vector_layers": [
+                        { "id": "roads", "minzoom": 4, "maxzoom": 14, "fields": { "lanes": "Number" } }
+                    ],
+                    "tilestats": {
+                        "layerCount": 1,
+                        "layers": [
+                            {
+                                "layer": "roads",
+                                "count": 2,
+                                "geometry": "LineString",
+                                "attributeCount": 1,
+                                "attributes": [
+                                    { "attribute": "lanes", "count": 2, "type": "number", "values": [1, 2], "min": 1, "max": 2 }
+                                ]
+                            }
+                        ]
+                    }
+                }"#,
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_from_mbtiles_rows() {
+        let meta = Metadata::from_mbtiles_rows(rows()).unwrap();
+        assert_eq!(meta.name, "roads");
+        assert_eq!(meta.version, "2");
+        assert_eq!(meta.scheme, Scheme::Xyz);
+        assert_eq!((meta.r#type, meta.extension.as_str()), (SourceType::Vector, "pbf"));
+        assert_eq!(meta.faces, vec![Face::Face0]);
+        assert_eq!(meta.bounds, BBox::new(-180., -85.05, 180., 85.05));
+        assert_eq!(meta.center, Some([10.5, 20., 4.]));
+        assert_eq!((meta.minzoom, meta.maxzoom), (0, 14));
+        assert_eq!(meta.attributions.get("OSM"), Some(&"https://openstreetmap.org".into()));
+        assert_eq!(meta.tiles, None);
+        assert_eq!(meta.get_extra::<String>("generator"), Some("tippecanoe v2.5.0".into()));
+        assert_eq!(meta.vector_layers[0].minzoom, Some(4));
+        let roads = &meta.layers["roads"];
+        assert_eq!((roads.minzoom, roads.draw_types.as_slice()), (4, &[DrawType::Lines][..]));
+        assert_eq!(serde_json::to_string(&roads.shape).unwrap(), r#"{"lanes":"u64"}"#);
+        let stats = meta.tippecanoe_tilestats.as_ref().unwrap();
+        assert_eq!(stats.layers[0].geometry, TippecanoeGeometry::LineString);
+
+        let raster = Metadata::from_mbtiles_rows([("format", "webp"), ("center", "1,2")]).unwrap();
+        assert_eq!((raster.r#type, raster.extension.as_str()), (SourceType::Raster, "webp"));
+        assert_eq!(raster.center, Some([1., 2., 0.]));
+    }
+
+    #[test]
+    fn test_from_mbtiles_rows_errors() {
+        let err = Metadata::from_mbtiles_rows([("bounds", "1,2,3")]).unwrap_err();
+        assert_eq!(err.to_string(), "invalid MBTiles metadata row bounds: \"1,2,3\"");
+        let err = Metadata::from_mbtiles_rows([("maxzoom", "z14")]).unwrap_err();
+        assert_eq!(err.to_string(), "invalid MBTiles metadata row maxzoom: \"z14\"");
+        let err = Metadata::from_mbtiles_rows([("json", "[]")]).unwrap_err();
+        assert!(matches!(err, MBTilesError::InvalidJson(_)));
+    }
+
+    #[test]
+    fn test_to_mbtiles_rows() {
+        let meta = Metadata::from_mbtiles_rows(rows()).unwrap();
+        let rows = meta.to_mbtiles_rows();
+        let get = |name: &str| rows.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+        assert_eq!(get("name"), Some("roads"));
+        assert_eq!(get("format"), Some("pbf"));
+        assert_eq!(get("type"), Some("overlay"));
+        assert_eq!(get("bounds"), Some("-180,-85.05,180,85.05"));
+        assert_eq!(get("center"), Some("10.5,20,4"));
+        assert_eq!(get("minzoom"), Some("0"));
+        assert_eq!(get("maxzoom"), Some("14"));
+        assert_eq!(get("attribution"), Some("<a href=\"https://openstreetmap.org\">OSM</a>"));
+        assert_eq!(get("generator"), Some("tippecanoe v2.5.0"));
+        let json: Value = serde_json::from_str(get("json").unwrap()).unwrap();
+        assert_eq!(json["vector_layers"][0]["id"], "roads");
+        assert_eq!(json["tilestats"]["layerCount"], 1);
+
+        // round trip
+        let back = Metadata::from_mbtiles_rows(rows.clone()).unwrap();
+        assert_eq!(back, meta);
+    }
+}
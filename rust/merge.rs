@@ -0,0 +1,599 @@
+use crate::{
+    DetailedTileStats, FACES, Metadata, MetadataBuilder, TileCoverage, TileStatsMetadata,
+    VectorLayer, merge_coverage, tile_zooms,
+};
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+use serde::Serialize;
+
+/// A value that differed between two merged Metadata. The value of the metadata being merged
+/// into is kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// JSON path of the conflicting value, e.g. `$.scheme`
+    pub path: String,
+    /// The JSON encoded value that was kept
+    pub ours: String,
+    /// The JSON encoded value that was discarded
+    pub theirs: String,
+}
+impl MergeConflict {
+    fn new<T: Serialize + ?Sized>(path: String, ours: &T, theirs: &T) -> Self {
+        let json = |v: &T| serde_json::to_string(v).unwrap_or_default();
+        MergeConflict { path, ours: json(ours), theirs: json(theirs) }
+    }
+}
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: kept {} over {}", self.path, self.ours, self.theirs)
+    }
+}
+
+impl Metadata {
+    /// Merge another Metadata into this one, e.g. to stitch tilesets built in separate jobs.
+    ///
+    /// `faces` are unioned, `bounds`, `minzoom`/`maxzoom`, `wmbounds` and `s2bounds` are widened,
+    /// `tilestats` are summed and `attributions` are unioned. Layers are reconciled by unioning
+    /// their draw types, widening their zoom ranges and merging their shapes, and the vector
+    /// layers are ordered by id. The center is recomputed from the merged bounds.
+    ///
+    /// Values that can't be reconciled, such as a differing `scheme`, `encoding` or `extension`,
+    /// keep the value of `self` and are returned as a [`MergeConflict`].
+    pub fn merge(&mut self, other: &Metadata) -> Vec<MergeConflict> {
+        let mut conflicts = Vec::new();
+        if self.coverage.is_some() || other.coverage.is_some() {
+            let ours_zooms = tile_zooms(self);
+            let mut coverage = self.coverage.take().unwrap_or_default();
+            merge_coverage(&mut coverage, &ours_zooms, other.coverage.as_ref(), &tile_zooms(other));
+            self.coverage = Some(coverage).filter(|c| !c.is_empty());
+        }
+        for face in &other.faces {
+            if !self.faces.contains(face) {
+                self.faces.push(*face);
+            }
+        }
+        self.faces.sort();
+        self.bounds.merge_in_place(&other.bounds);
+        self.merge_contents(other, &mut conflicts);
+        self.centerpoint.lon = (self.bounds.left + self.bounds.right) / 2.;
+        self.centerpoint.lat = (self.bounds.bottom + self.bounds.top) / 2.;
+        self.centerpoint.zoom = (self.minzoom + self.maxzoom) >> 1;
+
+        conflicts
+    }
+
+    /// Merge everything but the faces, bounds, center and coverage which are tracked
+    /// separately by the [`MetadataBuilder`]
+    pub(crate) fn merge_contents(&mut self, other: &Metadata, conflicts: &mut Vec<MergeConflict>) {
+        if self.scheme != other.scheme {
+            conflicts.push(MergeConflict::new("$.scheme".into(), &self.scheme, &other.scheme));
+        }
+        if self.encoding != other.encoding {
+            conflicts.push(MergeConflict::new(
+                "$.encoding".into(),
+                &self.encoding,
+                &other.encoding,
+            ));
+        }
+        if self.extension != other.extension {
+            conflicts.push(MergeConflict::new(
+                "$.extension".into(),
+                &self.extension,
+                &other.extension,
+            ));
+        }
+        if self.r#type != other.r#type {
+            conflicts.push(MergeConflict::new("$.type".into(), &self.r#type, &other.r#type));
+        }
+        if self.interval != other.interval && self.interval.is_some() && other.interval.is_some() {
+            conflicts.push(MergeConflict::new(
+                "$.interval".into(),
+                &self.interval,
+                &other.interval,
+            ));
+        }
+
+        self.minzoom = self.minzoom.min(other.minzoom);
+        self.maxzoom = self.maxzoom.max(other.maxzoom);
+        for (zoom, bbox) in &other.wmbounds {
+            self.wmbounds.entry(*zoom).and_modify(|b| b.merge_in_place(bbox)).or_insert(*bbox);
+        }
+        for face in FACES {
+            let bounds = self.s2bounds.get_mut(face);
+            for (zoom, bbox) in other.s2bounds.get(face) {
+                bounds.entry(*zoom).and_modify(|b| b.merge_in_place(bbox)).or_insert(*bbox);
+            }
+        }
+        self.tilestats.merge(&other.tilestats);
+        if let Some(stats) = &other.detailed_tilestats {
+            self.detailed_tilestats.get_or_insert_default().merge(stats);
+        }
+        if let Some(stats) = &other.tippecanoe_tilestats {
+            self.tippecanoe_tilestats.get_or_insert_default().merge(stats);
+        }
+
+        for (name, href) in other.attributions.iter() {
+            match self.attributions.get(name) {
+                Some(ours) if ours != href => conflicts.push(MergeConflict::new(
+                    format!("$.attributions[\"{name}\"]"),
+                    ours,
+                    href,
+                )),
+                Some(_) => {}
+                None => {
+                    self.attributions.insert(name.clone(), href.clone());
+                }
+            }
+        }
+        for (name, layer) in &other.layers {
+            match self.layers.get_mut(name) {
+                Some(ours) => {
+                    if let Err(err) = ours.union(layer) {
+                        let path = format!("$.layers[\"{name}\"].{}", err.path);
+                        conflicts.push(MergeConflict::new(path, &err.ours, &err.theirs));
+                    }
+                }
+                None => {
+                    self.layers.insert(name.clone(), layer.clone());
+                }
+            }
+        }
+        for layer in &other.vector_layers {
+            match self.vector_layers.iter_mut().find(|l| l.id == layer.id) {
+                Some(ours) => merge_vector_layer(ours, layer),
+                None => self.vector_layers.push(layer.clone()),
+            }
+        }
+        self.vector_layers.sort_by(|a, b| a.id.cmp(&b.id));
+
+        if self.interval.is_none() {
+            self.interval = other.interval;
+        }
+        if self.fillzoom.is_none() {
+            self.fillzoom = other.fillzoom;
+        }
+        if let Some(tiles) = &other.tiles {
+            let ours = self.tiles.get_or_insert_default();
+            for tile in tiles {
+                if !ours.contains(tile) {
+                    ours.push(tile.clone());
+                }
+            }
+        }
+        for (key, value) in &other.extra {
+            match self.extra.get(key) {
+                Some(ours) if ours != value => {
+                    conflicts.push(MergeConflict::new(format!("$.{key}"), ours, value))
+                }
+                Some(_) => {}
+                None => {
+                    self.extra.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+impl MetadataBuilder {
+    /// Absorb an already built Metadata, e.g. one produced by a separate job.
+    ///
+    /// Behaves like [`Metadata::merge`], except that the faces, bounds and center are folded
+    /// into the builder state and computed on [`MetadataBuilder::commit`].
+    pub fn absorb(&mut self, other: Metadata) -> Vec<MergeConflict> {
+        let mut conflicts = Vec::new();
+        if let Some(coverage) = &mut self.coverage {
+            let ours_zooms = tile_zooms(&self.metadata);
+            let theirs_zooms = tile_zooms(&other);
+            let dropped =
+                merge_coverage(coverage, &ours_zooms, other.coverage.as_ref(), &theirs_zooms);
+            self.degraded_zooms.extend(dropped);
+        }
+        self.faces.extend(other.faces.iter().copied());
+        self.update_lon_lat_bounds(&other.bounds);
+        self.metadata.merge_contents(&other, &mut conflicts);
+
+        conflicts
+    }
+}
+
+impl MetadataBuilder {
+    /// Fork a lightweight partial builder, e.g. one per worker thread.
+    ///
+    /// The fork keeps the settings, attributions and layers of this builder but none of its
+    /// tiles. Partial builders are combined with [`MetadataBuilder::merge`].
+    pub fn fork(&self) -> MetadataBuilder {
+        let default = MetadataBuilder::default();
+        MetadataBuilder {
+            metadata: Metadata {
+                wmbounds: default.metadata.wmbounds,
+                s2bounds: default.metadata.s2bounds,
+                tilestats: default.metadata.tilestats,
+                detailed_tilestats: self
+                    .metadata
+                    .detailed_tilestats
+                    .as_ref()
+                    .map(|_| DetailedTileStats::default()),
+                tippecanoe_tilestats: None,
+                ..self.metadata.clone()
+            },
+            coverage: self.coverage.as_ref().map(|_| TileCoverage::default()),
+            coverage_cap: self.coverage_cap,
+            degraded_zooms: self.degraded_zooms.clone(),
+            ..default
+        }
+    }
+
+    /// Combine two partial builders.
+    ///
+    /// The tiles of both builders are combined so that [`MetadataBuilder::commit`] returns the
+    /// same Metadata as if every tile had been added to a single builder. Combining tiles is
+    /// associative and commutative, so partial builders can be merged in any order. Settings
+    /// are taken from `self` while attributions and layers are added as if `other` added them
+    /// after `self`, so they should be set before forking. Layers whose shapes can't be unioned
+    /// keep the definition of `self` and are returned as a [`MergeConflict`]. The vector layers
+    /// are ordered by id on commit, whatever order the layers were merged in.
+    pub fn merge(mut self, other: MetadataBuilder) -> (MetadataBuilder, Vec<MergeConflict>) {
+        let mut conflicts = Vec::new();
+        if let Some(coverage) = &mut self.coverage {
+            let ours_zooms = tile_zooms(&self.metadata);
+            let theirs_zooms = tile_zooms(&other.metadata);
+            let dropped =
+                merge_coverage(coverage, &ours_zooms, other.coverage.as_ref(), &theirs_zooms);
+            self.degraded_zooms.extend(dropped);
+            self.degraded_zooms.extend(other.degraded_zooms.iter().copied());
+            for zoom in &self.degraded_zooms {
+                coverage.remove_zoom(*zoom);
+            }
+        }
+        self.faces.extend(other.faces);
+        self.update_lon_lat_bounds(&other.lon_lat_bounds);
+
+        let other = other.metadata;
+        for (zoom, bbox) in &other.wmbounds {
+            self.metadata
+                .wmbounds
+                .entry(*zoom)
+                .and_modify(|b| b.merge_in_place(bbox))
+                .or_insert(*bbox);
+        }
+        for face in FACES {
+            let bounds = self.metadata.s2bounds.get_mut(face);
+            for (zoom, bbox) in other.s2bounds.get(face) {
+                bounds.entry(*zoom).and_modify(|b| b.merge_in_place(bbox)).or_insert(*bbox);
+            }
+        }
+        self.metadata.tilestats.merge(&other.tilestats);
+        if let Some(stats) = &other.detailed_tilestats {
+            self.metadata.detailed_tilestats.get_or_insert_default().merge(stats);
+        }
+        if let Some(stats) = &other.tippecanoe_tilestats {
+            self.metadata.tippecanoe_tilestats.get_or_insert_default().merge(stats);
+        }
+        for (name, href) in other.attributions.iter() {
+            self.add_attribution(name, href);
+        }
+        for (name, layer) in &other.layers {
+            if let Err(err) = self.add_layer(name, layer) {
+                let path = format!("$.layers[\"{name}\"].{}", err.path);
+                conflicts.push(MergeConflict::new(path, &err.ours, &err.theirs));
+            }
+        }
+        for layer in &other.vector_layers {
+            match self.metadata.vector_layers.iter_mut().find(|l| l.id == layer.id) {
+                Some(ours) => merge_vector_layer(ours, layer),
+                None => self.metadata.vector_layers.push(layer.clone()),
+            }
+        }
+        self.metadata.minzoom = self.metadata.minzoom.min(other.minzoom);
+        self.metadata.maxzoom = self.metadata.maxzoom.max(other.maxzoom);
+
+        (self, conflicts)
+    }
+}
+
+impl TileStatsMetadata {
+    /// Add the tile counts of another tilestats to this one
+    pub fn merge(&mut self, other: &TileStatsMetadata) {
+        self.total += other.total;
+        self.total_0 += other.total_0;
+        self.total_1 += other.total_1;
+        self.total_2 += other.total_2;
+        self.total_3 += other.total_3;
+        self.total_4 += other.total_4;
+        self.total_5 += other.total_5;
+    }
+}
+
+/// Widen the zoom range and union the fields of a legacy vector layer
+fn merge_vector_layer(ours: &mut VectorLayer, theirs: &VectorLayer) {
+    if ours.description.is_none() {
+        ours.description = theirs.description.clone();
+    }
+    ours.minzoom = match (ours.minzoom, theirs.minzoom) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    ours.maxzoom = match (ours.maxzoom, theirs.maxzoom) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    };
+    for (key, value) in &theirs.fields {
+        ours.fields.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BBox, Center, DrawType, Encoding, Face, LayerMetaData, LonLatBounds, Scheme};
+    use alloc::{collections::BTreeMap, string::ToString, vec};
+
+    fn layer(minzoom: u8, maxzoom: u8, draw_types: &[DrawType], shape: &str) -> LayerMetaData {
+        LayerMetaData {
+            minzoom,
+            maxzoom,
+            draw_types: draw_types.to_vec(),
+            shape: serde_json::from_str(shape).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    fn europe() -> Metadata {
+        let mut builder = MetadataBuilder::default();
+        builder.set_name("world".into());
+        builder.add_attribution("OSM", "https://osm.org");
+        builder
+            .add_layer("water", &layer(0, 10, &[DrawType::Polygons], r#"{ "class": "string" }"#))
+            .unwrap();
+        builder.add_tile_s2(Face::Face0, 5, 22, 17, &LonLatBounds::new(-10., 35., 30., 70.));
+        builder.add_tile_s2(Face::Face0, 6, 44, 34, &LonLatBounds::new(-10., 35., 30., 70.));
+        builder.commit()
+    }
+
+    fn asia() -> Metadata {
+        let mut builder = MetadataBuilder::default();
+        builder.set_name("asia".into());
+        builder.add_attribution("OSM", "https://osm.org");
+        builder.add_attribution("Natural Earth", "https://naturalearthdata.com");
+        builder
+            .add_layer(
+                "water",
+                &layer(2, 12, &[DrawType::Lines], r#"{ "class": "string", "depth": "f64" }"#),
+            )
+            .unwrap();
+        builder
+            .add_layer("roads", &layer(4, 14, &[DrawType::Lines], r#"{ "kind": "string" }"#))
+            .unwrap();
+        builder.add_tile_s2(Face::Face1, 5, 3, 4, &LonLatBounds::new(60., 10., 140., 55.));
+        builder.add_tile_s2(Face::Face0, 5, 20, 20, &LonLatBounds::new(20., 30., 60., 50.));
+        builder.commit()
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut meta = europe();
+        let conflicts = meta.merge(&asia());
+        assert_eq!(conflicts, vec![]);
+        assert_eq!(meta.name, "world");
+        assert_eq!(meta.faces, vec![Face::Face0, Face::Face1]);
+        assert_eq!(meta.bounds, BBox::new(-10., 10., 140., 70.));
+        assert_eq!(meta.centerpoint, Center { lon: 65., lat: 40., zoom: 7 });
+        assert_eq!((meta.minzoom, meta.maxzoom), (0, 14));
+        assert_eq!(meta.s2bounds.face0.get(&5), Some(&BBox::new(20, 17, 22, 20)));
+        assert_eq!(meta.s2bounds.face0.get(&6), Some(&BBox::new(44, 34, 44, 34)));
+        assert_eq!(meta.s2bounds.face1.get(&5), Some(&BBox::new(3, 4, 3, 4)));
+        assert_eq!(meta.tilestats.total, 4);
+        assert_eq!(meta.tilestats.total_0, 3);
+        assert_eq!(meta.tilestats.total_1, 1);
+        assert_eq!(meta.attributions.len(), 2);
+        assert_eq!(
+            meta.layers,
+            BTreeMap::from([
+                ("roads".into(), layer(4, 14, &[DrawType::Lines], r#"{ "kind": "string" }"#)),
+                (
+                    "water".into(),
+                    layer(
+                        0,
+                        12,
+                        &[DrawType::Lines, DrawType::Polygons],
+                        r#"{ "class": "string", "depth": "f64" }"#
+                    )
+                ),
+            ])
+        );
+        let water = meta.vector_layers.iter().find(|l| l.id == "water").unwrap();
+        assert_eq!((water.minzoom, water.maxzoom), (Some(0), Some(12)));
+        assert_eq!(water.fields.len(), 2);
+        assert_eq!(meta.vector_layers.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_conflicts() {
+        let mut meta = europe();
+        let mut other = asia();
+        other.scheme = Scheme::Xyz;
+        other.encoding = Encoding::Gzip;
+        other.extension = "mvt".into();
+        other.attributions.insert("OSM".into(), "https://openstreetmap.org".into());
+        other.layers.get_mut("water").unwrap().shape =
+            serde_json::from_str(r#"{ "class": "u64", "depth": "f64" }"#).unwrap();
+        other.set_extra("license", &"ODbL");
+        meta.set_extra("license", &"CC-BY");
+
+        let conflicts = meta.merge(&other);
+        assert_eq!(
+            conflicts.iter().map(|c| c.path.as_str()).collect::<Vec<_>>(),
+            vec![
+                "$.scheme",
+                "$.encoding",
+                "$.extension",
+                "$.attributions[\"OSM\"]",
+                "$.layers[\"water\"].shape.class",
+                "$.license",
+            ]
+        );
+        assert_eq!(conflicts[0].to_string(), "$.scheme: kept \"fzxy\" over \"xyz\"");
+        // ours is kept
+        assert_eq!(meta.scheme, Scheme::Fzxy);
+        assert_eq!(meta.extension, "pbf");
+        assert_eq!(meta.attributions.get("OSM").unwrap(), "https://osm.org");
+        assert_eq!(meta.get_extra::<String>("license"), Some("CC-BY".into()));
+    }
+
+    #[test]
+    fn test_merge_coverage() {
+        let mut meta = europe();
+        let mut other = asia();
+        other.coverage = Some(TileCoverage::default());
+        other.coverage.as_mut().unwrap().insert_s2(Face::Face1, 5, 3, 4);
+        // face 0 zoom 5 is not tracked by either side, face 1 zoom 5 only exists in other
+        meta.merge(&other);
+        let coverage = meta.coverage.unwrap();
+        assert!(coverage.contains_s2(Face::Face1, 5, 3, 4));
+        assert!(!coverage.has_zoom_s2(Face::Face0, 5));
+    }
+
+    #[test]
+    fn test_absorb() {
+        let mut builder = MetadataBuilder::default();
+        builder.set_name("world".into());
+        builder.add_attribution("OSM", "https://osm.org");
+        builder
+            .add_layer("water", &layer(0, 10, &[DrawType::Polygons], r#"{ "class": "string" }"#))
+            .unwrap();
+        builder.add_tile_s2(Face::Face0, 5, 22, 17, &LonLatBounds::new(-10., 35., 30., 70.));
+        builder.add_tile_s2(Face::Face0, 6, 44, 34, &LonLatBounds::new(-10., 35., 30., 70.));
+        let conflicts = builder.absorb(asia());
+        assert_eq!(conflicts, vec![]);
+        let absorbed = builder.commit();
+
+        let mut merged = europe();
+        merged.merge(&asia());
+        assert_eq!(absorbed, merged);
+    }
+
+    /// A deterministic spread of tiles across faces and zooms
+    fn tiles() -> Vec<(Face, u8, u32, u32, LonLatBounds)> {
+        let mut tiles = Vec::new();
+        for i in 0..60_u32 {
+            let face = Face::from((i % 6) as u8);
+            let zoom = (i % 4) as u8 + 2;
+            let (x, y) = ((i * 7) % (1 << zoom), (i * 3) % (1 << zoom));
+            let ll = LonLatBounds::new(i as f64 - 90., i as f64 / 2. - 45., i as f64 - 80., 10.);
+            tiles.push((face, zoom, x, y, ll));
+        }
+        tiles
+    }
+
+    fn base() -> MetadataBuilder {
+        let mut builder = MetadataBuilder::default();
+        builder.set_name("parallel".into());
+        builder.add_attribution("OSM", "https://osm.org");
+        builder
+            .add_layer("water", &layer(0, 10, &[DrawType::Polygons], r#"{ "class": "string" }"#))
+            .unwrap();
+        builder.enable_coverage(40);
+        builder
+    }
+
+    #[test]
+    fn test_builder_merge() {
+        let mut sequential = base();
+        for (face, zoom, x, y, ll) in tiles() {
+            sequential.add_tile_s2(face, zoom, x, y, &ll);
+            sequential.add_tile_wm(zoom, x, y, &ll);
+        }
+        let expected = sequential.commit();
+        assert!(expected.coverage.is_some());
+
+        // split the tiles across 3 workers
+        let main = base();
+        let mut workers = vec![main.fork(), main.fork(), main.fork()];
+        for (i, (face, zoom, x, y, ll)) in tiles().into_iter().enumerate() {
+            workers[i % 3].add_tile_s2(face, zoom, x, y, &ll);
+            workers[(i + 1) % 3].add_tile_wm(zoom, x, y, &ll);
+        }
+        let [a, b, c]: [MetadataBuilder; 3] = workers.try_into().unwrap();
+
+        let merge = |a: MetadataBuilder, b: MetadataBuilder| {
+            let (merged, conflicts) = a.merge(b);
+            assert!(conflicts.is_empty());
+            merged
+        };
+        let left = merge(merge(main.clone(), merge(a.clone(), b.clone())), c.clone());
+        let right = merge(main.clone(), merge(c.clone(), merge(b.clone(), a.clone())));
+        let reversed = merge(merge(merge(main.clone(), c), b), a);
+        for mut builder in [left, right, reversed] {
+            assert_eq!(builder.commit(), expected);
+            // committing is idempotent
+            assert_eq!(builder.commit(), expected);
+        }
+    }
+
+    #[test]
+    fn test_builder_merge_degraded() {
+        let mut main = base();
+        main.absorb(Metadata {
+            wmbounds: BTreeMap::from([(2, BBox::new(0, 0, 3, 3))]),
+            ..Default::default()
+        });
+        let mut a = main.fork();
+        a.add_tile_wm(2, 1, 1, &LonLatBounds::new(0., 0., 1., 1.));
+        a.add_tile_wm(3, 1, 1, &LonLatBounds::new(0., 0., 1., 1.));
+        let meta = main.merge(a).0.commit();
+        let coverage = meta.coverage.unwrap();
+        assert!(!coverage.has_zoom_wm(2));
+        assert!(coverage.contains_wm(3, 1, 1));
+    }
+
+    #[test]
+    fn test_builder_merge_conflict() {
+        let main = base();
+        let (mut a, mut b) = (main.fork(), main.fork());
+        a.add_layer("roads", &layer(0, 10, &[DrawType::Lines], r#"{ "a": "string" }"#)).unwrap();
+        b.add_layer("roads", &layer(0, 10, &[DrawType::Lines], r#"{ "a": "bool" }"#)).unwrap();
+
+        let (_, conflicts) = a.clone().merge(b.clone());
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].path, "$.layers[\"roads\"].shape.a");
+        assert_eq!(
+            (conflicts[0].ours.as_str(), conflicts[0].theirs.as_str()),
+            ("\"string\"", "\"bool\"")
+        );
+        let (_, conflicts) = b.merge(a);
+        assert_eq!(conflicts[0].path, "$.layers[\"roads\"].shape.a");
+    }
+
+    #[test]
+    fn test_builder_merge_layers_commutative() {
+        let main = base();
+        let (mut a, mut b) = (main.fork(), main.fork());
+        a.add_layer("roads", &layer(2, 8, &[DrawType::Lines], r#"{ "kind": "string" }"#)).unwrap();
+        a.add_tile_wm(1, 0, 0, &LonLatBounds::new(-180., 0., 0., 85.));
+        b.add_layer("places", &layer(0, 4, &[DrawType::Points], r#"{ "rank": "u64" }"#)).unwrap();
+        // a layer absorbed without a vector layer
+        b.absorb(Metadata {
+            layers: BTreeMap::from([(
+                "only_layers".into(),
+                layer(3, 5, &[DrawType::Polygons], r#"{ "area": "f64" }"#),
+            )]),
+            ..Default::default()
+        });
+        b.add_tile_wm(1, 1, 0, &LonLatBounds::new(0., 0., 180., 85.));
+
+        let (mut ab, conflicts) = a.clone().merge(b.clone());
+        assert_eq!(conflicts, vec![]);
+        let (mut ba, conflicts) = b.merge(a);
+        assert_eq!(conflicts, vec![]);
+        let (ab, ba) = (ab.commit(), ba.commit());
+        assert_eq!(ab, ba);
+        assert_eq!(
+            ab.layers.keys().collect::<Vec<_>>(),
+            vec!["only_layers", "places", "roads", "water"]
+        );
+        assert_eq!(
+            ab.vector_layers.iter().map(|l| l.id.as_str()).collect::<Vec<_>>(),
+            vec!["only_layers", "places", "roads", "water"]
+        );
+    }
+}
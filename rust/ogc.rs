@@ -0,0 +1,418 @@
+use crate::{DrawType, LayerMetaData, Metadata, Scheme, SourceType, attributions_to_html};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// The OGC URI of the WebMercatorQuad TileMatrixSet
+pub const WEB_MERCATOR_QUAD_URI: &str =
+    "http://www.opengis.net/def/tilematrixset/OGC/1.0/WebMercatorQuad";
+/// The OGC URI of the Web Mercator CRS
+const EPSG_3857_URI: &str = "http://www.opengis.net/def/crs/EPSG/0/3857";
+/// The OGC URI of the longitude-latitude CRS
+const CRS84_URI: &str = "http://www.opengis.net/def/crs/OGC/1.3/CRS84";
+
+/// Reasons a Metadata can't be described as an OGC tileset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OGCError {
+    /// S2 tilesets (`fzxy` and `tfzxy`) have no OGC TileMatrixSet equivalent
+    S2Scheme(Scheme),
+}
+impl fmt::Display for OGCError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OGCError::S2Scheme(scheme) => {
+                let scheme: &str = (*scheme).into();
+                write!(f, "the {scheme} scheme has no OGC TileMatrixSet equivalent")
+            }
+        }
+    }
+}
+
+/// The tile index range of a single zoom
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OGCTileMatrixSetLimit {
+    /// The zoom, e.g. `"5"`
+    pub tile_matrix: String,
+    /// First row (y) with data
+    pub min_tile_row: u64,
+    /// Last row (y) with data
+    pub max_tile_row: u64,
+    /// First column (x) with data
+    pub min_tile_col: u64,
+    /// Last column (x) with data
+    pub max_tile_col: u64,
+}
+
+/// A link of an OGC tileset
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct OGCLink {
+    /// The relation type
+    pub rel: String,
+    /// The URL or URL template
+    pub href: String,
+    /// The media type of the resource
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<String>,
+    /// Whether `href` is a URL template
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub templated: Option<bool>,
+}
+
+/// A layer of an OGC vector tileset
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OGCLayer {
+    /// The layer name
+    pub id: String,
+    /// Always `vector`
+    pub data_type: String,
+    /// The layer description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The first zoom of the layer
+    pub min_tile_matrix: String,
+    /// The last zoom of the layer
+    pub max_tile_matrix: String,
+    /// 0 for points, 1 for lines and 2 for polygons. Absent if the layer mixes them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geometry_dimension: Option<u8>,
+}
+
+/// A bounding box in CRS84
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OGCBoundingBox {
+    /// `[west, south]`
+    pub lower_left: [f64; 2],
+    /// `[east, north]`
+    pub upper_right: [f64; 2],
+    /// The CRS of the coordinates
+    pub crs: String,
+}
+
+/// The center of an OGC tileset
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OGCCenterPoint {
+    /// `[lon, lat]`
+    pub coordinates: [f64; 2],
+    /// The zoom to display the center at
+    pub tile_matrix: String,
+    /// The CRS of the coordinates
+    pub crs: String,
+}
+
+/// # OGC Tileset
+///
+/// ## Description
+/// The tileset metadata of [OGC API - Tiles](https://docs.ogc.org/is/20-057/20-057.html) using
+/// the `WebMercatorQuad` TileMatrixSet. Built with [`Metadata::to_ogc_tileset`].
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OGCTileset {
+    /// The name of the tileset
+    pub title: String,
+    /// The description of the tileset
+    pub description: String,
+    /// `vector`, `map` or `coverage`
+    pub data_type: String,
+    /// The CRS of the TileMatrixSet
+    pub crs: String,
+    /// Always [`WEB_MERCATOR_QUAD_URI`]
+    #[serde(rename = "tileMatrixSetURI")]
+    pub tile_matrix_set_uri: String,
+    /// The tile index range of each zoom with data
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tile_matrix_set_limits: Vec<OGCTileMatrixSetLimit>,
+    /// The tiling scheme and tile URL templates
+    pub links: Vec<OGCLink>,
+    /// The vector layers
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub layers: Vec<OGCLayer>,
+    /// The extent of the data
+    pub bounding_box: OGCBoundingBox,
+    /// The center of the data
+    pub center_point: OGCCenterPoint,
+    /// HTML attribution
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<String>,
+    /// The version of the data
+    pub version: String,
+}
+
+impl Metadata {
+    /// Describe the tileset as OGC API - Tiles tileset metadata.
+    ///
+    /// The TileMatrixSetLimits are taken from `wmbounds`, skipping bounds outside of their zoom,
+    /// the layers from `layers` and the tile URL templates from `tiles`. Templates that can't be expressed with `{tileMatrix}`,
+    /// `{tileRow}` and `{tileCol}` (e.g. time sensitive ones) are skipped.
+    /// Returns an [`OGCError`] for S2 tilesets.
+    pub fn to_ogc_tileset(&self) -> Result<OGCTileset, OGCError> {
+        if self.scheme.is_s2() {
+            return Err(OGCError::S2Scheme(self.scheme));
+        }
+        let media_type = match (self.r#type, self.extension.as_str()) {
+            (SourceType::Vector, _) => Some("application/vnd.mapbox-vector-tile"),
+            (_, "png") => Some("image/png"),
+            (_, "jpg" | "jpeg") => Some("image/jpeg"),
+            (_, "webp") => Some("image/webp"),
+            (_, "avif") => Some("image/avif"),
+            _ => None,
+        };
+
+        let mut links = Vec::from([OGCLink {
+            rel: "http://www.opengis.net/def/rel/ogc/1.0/tiling-scheme".into(),
+            href: WEB_MERCATOR_QUAD_URI.into(),
+            r#type: Some("application/json".into()),
+            templated: None,
+        }]);
+        for template in self.tiles.iter().flatten() {
+            if let Some(href) = ogc_template(template, self.scheme) {
+                links.push(OGCLink {
+                    rel: "item".into(),
+                    href,
+                    r#type: media_type.map(Into::into),
+                    templated: Some(true),
+                });
+            }
+        }
+
+        let attribution = if self.attributions.is_empty() {
+            self.attribution.clone()
+        } else {
+            Some(attributions_to_html(&self.attributions))
+        };
+
+        Ok(OGCTileset {
+            title: self.name.clone(),
+            description: self.description.clone(),
+            data_type: match self.r#type {
+                SourceType::Raster => "map",
+                SourceType::RasterDem => "coverage",
+                _ => "vector",
+            }
+            .into(),
+            crs: EPSG_3857_URI.into(),
+            tile_matrix_set_uri: WEB_MERCATOR_QUAD_URI.into(),
+            tile_matrix_set_limits: self
+                .wmbounds
+                .iter()
+                .filter_map(|(zoom, bounds)| {
+                    // bounds outside of the zoom's tile range are skipped
+                    let max = 1u64.checked_shl(*zoom as u32)? - 1;
+                    if [bounds.left, bounds.bottom, bounds.right, bounds.top]
+                        .iter()
+                        .any(|b| *b > max)
+                    {
+                        return None;
+                    }
+                    // OGC rows are counted from the north while TMS rows are counted from the south
+                    let (min_tile_row, max_tile_row) = match self.scheme {
+                        Scheme::Tms => (max - bounds.top, max - bounds.bottom),
+                        _ => (bounds.bottom, bounds.top),
+                    };
+                    Some(OGCTileMatrixSetLimit {
+                        tile_matrix: zoom.to_string(),
+                        min_tile_row,
+                        max_tile_row,
+                        min_tile_col: bounds.left,
+                        max_tile_col: bounds.right,
+                    })
+                })
+                .collect(),
+            links,
+            layers: self.layers.iter().map(|(name, layer)| ogc_layer(name, layer)).collect(),
+            bounding_box: OGCBoundingBox {
+                lower_left: [self.bounds.left, self.bounds.bottom],
+                upper_right: [self.bounds.right, self.bounds.top],
+                crs: CRS84_URI.into(),
+            },
+            center_point: OGCCenterPoint {
+                coordinates: [self.centerpoint.lon, self.centerpoint.lat],
+                tile_matrix: self.centerpoint.zoom.to_string(),
+                crs: CRS84_URI.into(),
+            },
+            attribution,
+            version: self.version.clone(),
+        })
+    }
+}
+
+/// Convert a `tiles` template to OGC placeholders. `None` if it can't be expressed
+fn ogc_template(template: &str, scheme: Scheme) -> Option<String> {
+    // OGC rows are counted top-down like xyz, so TMS templates need `{-y}`
+    let (row, flipped_row) = if scheme == Scheme::Tms { ("{-y}", "{y}") } else { ("{y}", "{-y}") };
+    if ["{t}", "{face}", "{quadkey}", flipped_row].iter().any(|p| template.contains(p)) {
+        return None;
+    }
+    Some(
+        template
+            .replace("{z}", "{tileMatrix}")
+            .replace("{x}", "{tileCol}")
+            .replace(row, "{tileRow}"),
+    )
+}
+
+/// Describe a layer. The geometry dimension is only set if every draw type agrees on it
+fn ogc_layer(name: &str, layer: &LayerMetaData) -> OGCLayer {
+    let mut dimensions = layer.draw_types.iter().filter_map(|draw_type| match draw_type {
+        DrawType::Points | DrawType::Points3D => Some(0),
+        DrawType::Lines | DrawType::Lines3D => Some(1),
+        DrawType::Polygons | DrawType::Polygons3D => Some(2),
+        _ => None,
+    });
+    let first = dimensions.next();
+    let geometry_dimension = first.filter(|d| dimensions.all(|other| other == *d));
+    OGCLayer {
+        id: name.into(),
+        data_type: "vector".into(),
+        description: layer.description.clone(),
+        min_tile_matrix: layer.minzoom.to_string(),
+        max_tile_matrix: layer.maxzoom.to_string(),
+        geometry_dimension,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BBox, Center, TileBounds};
+    use alloc::{collections::BTreeMap, vec};
+
+    fn meta() -> Metadata {
+        Metadata {
+            name: "roads".into(),
+            description: "roads of the world".into(),
+            scheme: Scheme::Xyz,
+            bounds: BBox::new(-10., -20., 30., 40.),
+            centerpoint: Center { lon: 10., lat: 10., zoom: 3 },
+            wmbounds: BTreeMap::from([
+                (0, TileBounds::new(0, 0, 0, 0)),
+                (2, TileBounds::new(1, 1, 2, 3)),
+            ]),
+            tiles: Some(vec![
+                "https://tiles.org/{z}/{x}/{y}.pbf".into(),
+                "https://tiles.org/{t}/{z}/{x}/{y}.pbf".into(),
+            ]),
+            layers: BTreeMap::from([
+                (
+                    "roads".into(),
+                    LayerMetaData {
+                        description: Some("major roads".into()),
+                        minzoom: 2,
+                        maxzoom: 14,
+                        draw_types: vec![DrawType::Lines, DrawType::Lines3D],
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "pois".into(),
+                    LayerMetaData {
+                        draw_types: vec![DrawType::Points, DrawType::Polygons],
+                        ..Default::default()
+                    },
+                ),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_to_ogc_tileset() {
+        let tileset = meta().to_ogc_tileset().unwrap();
+        assert_eq!(tileset.title, "roads");
+        assert_eq!(tileset.data_type, "vector");
+        assert_eq!(tileset.tile_matrix_set_uri, WEB_MERCATOR_QUAD_URI);
+        assert_eq!(
+            tileset.tile_matrix_set_limits[1],
+            OGCTileMatrixSetLimit {
+                tile_matrix: "2".into(),
+                min_tile_row: 1,
+                max_tile_row: 3,
+                min_tile_col: 1,
+                max_tile_col: 2,
+            }
+        );
+        assert_eq!(tileset.links.len(), 2);
+        assert_eq!(tileset.links[1].href, "https://tiles.org/{tileMatrix}/{tileCol}/{tileRow}.pbf");
+        assert_eq!(tileset.links[1].r#type.as_deref(), Some("application/vnd.mapbox-vector-tile"));
+        assert_eq!(
+            tileset.layers,
+            vec![
+                OGCLayer {
+                    id: "pois".into(),
+                    data_type: "vector".into(),
+                    description: None,
+                    min_tile_matrix: "0".into(),
+                    max_tile_matrix: "0".into(),
+                    geometry_dimension: None,
+                },
+                OGCLayer {
+                    id: "roads".into(),
+                    data_type: "vector".into(),
+                    description: Some("major roads".into()),
+                    min_tile_matrix: "2".into(),
+                    max_tile_matrix: "14".into(),
+                    geometry_dimension: Some(1),
+                },
+            ]
+        );
+
+        let json = serde_json::to_value(&tileset).unwrap();
+        assert_eq!(json["tileMatrixSetURI"], WEB_MERCATOR_QUAD_URI);
+        assert_eq!(json["tileMatrixSetLimits"][0]["maxTileRow"], 0);
+        assert_eq!(json["boundingBox"]["lowerLeft"], serde_json::json!([-10., -20.]));
+        assert_eq!(json["centerPoint"]["tileMatrix"], "3");
+        assert_eq!(json["layers"][1]["geometryDimension"], 1);
+        assert!(json.get("attribution").is_none());
+    }
+
+    #[test]
+    fn test_to_ogc_tileset_tms() {
+        let meta = Metadata {
+            scheme: Scheme::Tms,
+            r#type: SourceType::Raster,
+            extension: "png".into(),
+            tiles: Some(vec![
+                "https://a.org/{z}/{x}/{y}".into(),
+                "https://b.org/{z}/{x}/{-y}".into(),
+            ]),
+            ..meta()
+        };
+        let tileset = meta.to_ogc_tileset().unwrap();
+        assert_eq!(tileset.data_type, "map");
+        assert_eq!(tileset.links.len(), 2);
+        assert_eq!(tileset.links[1].href, "https://b.org/{tileMatrix}/{tileCol}/{tileRow}");
+        assert_eq!(tileset.links[1].r#type.as_deref(), Some("image/png"));
+        // the rows are flipped to count from the north
+        let limit = &tileset.tile_matrix_set_limits[1];
+        assert_eq!((limit.min_tile_row, limit.max_tile_row), (0, 2));
+        assert_eq!((limit.min_tile_col, limit.max_tile_col), (1, 2));
+
+        // tile bounds filled from lon-lat bounds agree with the Web Mercator rows
+        let mut meta =
+            Metadata { bounds: BBox::new(10., 10., 170., 80.), minzoom: 2, maxzoom: 2, ..meta };
+        meta.fill_tile_bounds();
+        let limit = &meta.to_ogc_tileset().unwrap().tile_matrix_set_limits[0];
+        assert_eq!((limit.min_tile_row, limit.max_tile_row), (0, 1));
+
+        // out of range zooms and bounds are skipped instead of overflowing
+        meta.wmbounds.insert(64, BBox::new(0, 0, 1, 1));
+        meta.wmbounds.insert(200, BBox::new(0, 0, 1, 1));
+        meta.wmbounds.insert(3, BBox::new(0, 0, 8, 8));
+        let limits = meta.to_ogc_tileset().unwrap().tile_matrix_set_limits;
+        assert_eq!(limits.iter().map(|l| l.tile_matrix.as_str()).collect::<Vec<_>>(), vec!["2"]);
+    }
+
+    #[test]
+    fn test_to_ogc_tileset_s2() {
+        let meta = Metadata { scheme: Scheme::Fzxy, ..meta() };
+        let err = meta.to_ogc_tileset().unwrap_err();
+        assert_eq!(err, OGCError::S2Scheme(Scheme::Fzxy));
+        assert_eq!(err.to_string(), "the fzxy scheme has no OGC TileMatrixSet equivalent");
+    }
+}
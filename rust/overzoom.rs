@@ -0,0 +1,195 @@
+use crate::{Metadata, TileRequest};
+
+/// The tile that actually holds the data for a request along with where the requested tile
+/// sits inside of it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedTile {
+    /// The tile to fetch
+    pub source: TileRequest,
+    /// How many requested tiles fit across the source tile (`2^(zoom - source.zoom)`)
+    pub scale: u64,
+    /// The x position of the requested tile inside the source tile, in units of requested tiles
+    pub offset_x: u64,
+    /// The y position of the requested tile inside the source tile, in units of requested tiles
+    pub offset_y: u64,
+}
+impl ResolvedTile {
+    /// Returns true if the requested tile is the source tile itself
+    pub fn is_exact(&self) -> bool {
+        self.scale == 1
+    }
+
+    /// The requested tile as a `[left, top, size]` fraction of the source tile, where `[0, 0, 1]`
+    /// is the entire source tile
+    pub fn fraction(&self) -> [f64; 3] {
+        let scale = self.scale as f64;
+        [self.offset_x as f64 / scale, self.offset_y as f64 / scale, 1. / scale]
+    }
+}
+
+impl Metadata {
+    /// Resolve the tile that holds the data of a requested tile.
+    ///
+    /// Requests inside the zoom range resolve to themselves and requests beyond `maxzoom` to
+    /// their ancestor at `maxzoom`. If that tile does not exist, its ancestor at `fillzoom` is
+    /// used instead when set. The scheme decides if the request is treated as S2
+    /// (`fzxy`/`tfzxy`) or Web Mercator. Returns `None` if the tile is below `minzoom` or no
+    /// source tile exists.
+    pub fn resolve_tile(&self, request: TileRequest) -> Option<ResolvedTile> {
+        let TileRequest { zoom, .. } = request;
+        if zoom >= 64 || zoom < self.minzoom {
+            return None;
+        }
+        let source_zoom = zoom.min(self.maxzoom);
+        self.resolve_at(request, source_zoom).or_else(|| {
+            let fillzoom = self.fillzoom.filter(|f| *f < source_zoom && *f >= self.minzoom)?;
+            self.resolve_at(request, fillzoom)
+        })
+    }
+
+    /// Resolve a request to its ancestor at `source_zoom` if that tile exists
+    fn resolve_at(&self, request: TileRequest, source_zoom: u8) -> Option<ResolvedTile> {
+        let TileRequest { face, zoom, x, y, .. } = request;
+        let shift = zoom - source_zoom;
+        let source = TileRequest { zoom: source_zoom, x: x >> shift, y: y >> shift, ..request };
+        let exists = if self.scheme.is_s2() {
+            self.has_tile_s2(face, source.zoom, source.x, source.y)
+        } else {
+            self.has_tile_wm(source.zoom, source.x, source.y)
+        };
+        if !exists {
+            return None;
+        }
+
+        Some(ResolvedTile {
+            source,
+            scale: 1 << shift,
+            offset_x: x - (source.x << shift),
+            offset_y: y - (source.y << shift),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BBox, Face, Scheme};
+    use alloc::{collections::BTreeMap, vec};
+
+    #[test]
+    fn test_resolve_wm() {
+        let meta = Metadata { scheme: Scheme::Xyz, minzoom: 2, maxzoom: 10, ..Default::default() };
+        // inside the zoom range
+        let req = TileRequest { zoom: 5, x: 3, y: 4, ..Default::default() };
+        let resolved = meta.resolve_tile(req).unwrap();
+        assert_eq!(resolved, ResolvedTile { source: req, scale: 1, offset_x: 0, offset_y: 0 });
+        assert!(resolved.is_exact());
+        assert_eq!(resolved.fraction(), [0., 0., 1.]);
+        // below minzoom
+        assert_eq!(meta.resolve_tile(TileRequest { zoom: 1, ..Default::default() }), None);
+        // overzoom from maxzoom
+        let req = TileRequest { zoom: 12, x: 2051, y: 1026, time: Some(5), ..Default::default() };
+        let resolved = meta.resolve_tile(req).unwrap();
+        assert_eq!(
+            resolved,
+            ResolvedTile {
+                source: TileRequest { zoom: 10, x: 512, y: 256, time: Some(5), ..req },
+                scale: 4,
+                offset_x: 3,
+                offset_y: 2,
+            }
+        );
+        assert!(!resolved.is_exact());
+        assert_eq!(resolved.fraction(), [0.75, 0.5, 0.25]);
+    }
+
+    #[test]
+    fn test_resolve_fillzoom() {
+        let mut meta = Metadata {
+            scheme: Scheme::Xyz,
+            minzoom: 0,
+            maxzoom: 10,
+            fillzoom: Some(7),
+            ..Default::default()
+        };
+        meta.wmbounds.insert(10, BBox::new(0, 0, 1023, 1023));
+        meta.wmbounds.insert(7, BBox::new(0, 0, 127, 127));
+        meta.wmbounds.insert(8, BBox::new(0, 0, 50, 50));
+        // a z11 tile uses the existing z10 tile rather than z7
+        let req = TileRequest { zoom: 11, x: 17, y: 33, ..Default::default() };
+        assert_eq!(
+            meta.resolve_tile(req),
+            Some(ResolvedTile {
+                source: TileRequest { zoom: 10, x: 8, y: 16, ..req },
+                scale: 2,
+                offset_x: 1,
+                offset_y: 1,
+            })
+        );
+        // inside the zoom range the tile is used as is
+        let req = TileRequest { zoom: 9, x: 17, y: 33, ..Default::default() };
+        assert!(meta.resolve_tile(req).unwrap().is_exact());
+        // a missing tile inside the zoom range falls back to the z7 tile
+        let req = TileRequest { zoom: 8, x: 100, y: 33, ..Default::default() };
+        assert_eq!(
+            meta.resolve_tile(req),
+            Some(ResolvedTile {
+                source: TileRequest { zoom: 7, x: 50, y: 16, ..req },
+                scale: 2,
+                offset_x: 0,
+                offset_y: 1,
+            })
+        );
+        // a missing z10 tile falls back to the z7 tile as well
+        meta.wmbounds.insert(10, BBox::new(0, 0, 7, 7));
+        let req = TileRequest { zoom: 12, x: 68, y: 132, ..Default::default() };
+        assert_eq!(
+            meta.resolve_tile(req),
+            Some(ResolvedTile {
+                source: TileRequest { zoom: 7, x: 2, y: 4, ..req },
+                scale: 32,
+                offset_x: 4,
+                offset_y: 4,
+            })
+        );
+        // without a fillzoom the missing tile is not resolved
+        meta.fillzoom = None;
+        assert_eq!(meta.resolve_tile(req), None);
+    }
+
+    #[test]
+    fn test_resolve_s2() {
+        let mut meta = Metadata {
+            scheme: Scheme::Fzxy,
+            minzoom: 0,
+            maxzoom: 5,
+            faces: vec![Face::Face1],
+            ..Default::default()
+        };
+        meta.s2bounds.get_mut(Face::Face1).insert(5, BBox::new(22, 17, 22, 17));
+        let req = TileRequest { face: Face::Face1, zoom: 8, x: 181, y: 140, time: None };
+        assert_eq!(
+            meta.resolve_tile(req),
+            Some(ResolvedTile {
+                source: TileRequest { zoom: 5, x: 22, y: 17, ..req },
+                scale: 8,
+                offset_x: 5,
+                offset_y: 4,
+            })
+        );
+        // the source tile does not exist
+        let req = TileRequest { face: Face::Face1, zoom: 8, x: 0, y: 0, time: None };
+        assert_eq!(meta.resolve_tile(req), None);
+        // the face does not exist
+        let req = TileRequest { face: Face::Face2, zoom: 8, x: 181, y: 140, time: None };
+        assert_eq!(meta.resolve_tile(req), None);
+
+        // wm bounds are used for wm schemes
+        meta.scheme = Scheme::Xyz;
+        meta.wmbounds = BTreeMap::from([(5, BBox::new(0, 0, 1, 1))]);
+        let req = TileRequest { zoom: 6, x: 3, y: 2, ..Default::default() };
+        assert_eq!(meta.resolve_tile(req).unwrap().source.x, 1);
+        let req = TileRequest { zoom: 6, x: 4, y: 2, ..Default::default() };
+        assert_eq!(meta.resolve_tile(req), None);
+    }
+}
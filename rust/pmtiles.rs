@@ -0,0 +1,570 @@
+use crate::{BBox, Center, Encoding, MapboxTileJSONMetadata, Metadata, Scheme, SourceType};
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+/// Size in bytes of a PMTiles v3 header
+pub const PMTILES_HEADER_SIZE: usize = 127;
+
+/// The magic bytes every PMTiles archive starts with
+const PMTILES_MAGIC: &[u8; 7] = b"PMTiles";
+
+/// Reasons a PMTiles header or its JSON metadata could not be read
+#[derive(Debug)]
+pub enum PMTilesError {
+    /// Fewer than [`PMTILES_HEADER_SIZE`] bytes were given. Contains the number of bytes
+    TooShort(usize),
+    /// The bytes don't start with `PMTiles`
+    InvalidMagic,
+    /// Only version 3 is supported. Contains the version found
+    UnsupportedVersion(u8),
+    /// The JSON metadata is not a valid metadata object
+    InvalidJson(serde_json::Error),
+}
+impl fmt::Display for PMTilesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PMTilesError::TooShort(len) => {
+                write!(f, "PMTiles header needs {PMTILES_HEADER_SIZE} bytes, got {len}")
+            }
+            PMTilesError::InvalidMagic => f.write_str("not a PMTiles archive"),
+            PMTilesError::UnsupportedVersion(v) => write!(f, "unsupported PMTiles version {v}"),
+            PMTilesError::InvalidJson(e) => write!(f, "invalid PMTiles JSON metadata: {e}"),
+        }
+    }
+}
+
+/// Compression ids of a PMTiles archive
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PMTilesCompression {
+    /// Unknown compression
+    #[default]
+    Unknown = 0,
+    /// No compression
+    None = 1,
+    /// Gzip compression
+    Gzip = 2,
+    /// Brotli compression
+    Brotli = 3,
+    /// Zstd compression
+    Zstd = 4,
+}
+impl From<u8> for PMTilesCompression {
+    fn from(compression: u8) -> Self {
+        match compression {
+            1 => PMTilesCompression::None,
+            2 => PMTilesCompression::Gzip,
+            3 => PMTilesCompression::Brotli,
+            4 => PMTilesCompression::Zstd,
+            _ => PMTilesCompression::Unknown,
+        }
+    }
+}
+impl From<Encoding> for PMTilesCompression {
+    fn from(encoding: Encoding) -> Self {
+        match encoding {
+            Encoding::None => PMTilesCompression::None,
+            Encoding::Gzip => PMTilesCompression::Gzip,
+            Encoding::Brotli => PMTilesCompression::Brotli,
+            Encoding::Zstd => PMTilesCompression::Zstd,
+        }
+    }
+}
+impl PMTilesCompression {
+    /// The matching [`Encoding`]. `None` if the compression is unknown
+    pub fn encoding(self) -> Option<Encoding> {
+        match self {
+            PMTilesCompression::Unknown => None,
+            PMTilesCompression::None => Some(Encoding::None),
+            PMTilesCompression::Gzip => Some(Encoding::Gzip),
+            PMTilesCompression::Brotli => Some(Encoding::Brotli),
+            PMTilesCompression::Zstd => Some(Encoding::Zstd),
+        }
+    }
+}
+
+/// Tile type ids of a PMTiles archive
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PMTilesTileType {
+    /// Unknown tile type
+    #[default]
+    Unknown = 0,
+    /// Mapbox Vector Tiles
+    Mvt = 1,
+    /// PNG images
+    Png = 2,
+    /// JPEG images
+    Jpeg = 3,
+    /// WebP images
+    Webp = 4,
+    /// AVIF images
+    Avif = 5,
+}
+impl From<u8> for PMTilesTileType {
+    fn from(tile_type: u8) -> Self {
+        match tile_type {
+            1 => PMTilesTileType::Mvt,
+            2 => PMTilesTileType::Png,
+            3 => PMTilesTileType::Jpeg,
+            4 => PMTilesTileType::Webp,
+            5 => PMTilesTileType::Avif,
+            _ => PMTilesTileType::Unknown,
+        }
+    }
+}
+impl PMTilesTileType {
+    /// Infer the tile type from the source type and the extension of a Metadata
+    pub fn from_metadata(r#type: SourceType, extension: &str) -> Self {
+        match (r#type, extension) {
+            (SourceType::Vector, _) => PMTilesTileType::Mvt,
+            (_, "png") => PMTilesTileType::Png,
+            (_, "jpg" | "jpeg") => PMTilesTileType::Jpeg,
+            (_, "webp") => PMTilesTileType::Webp,
+            (_, "avif") => PMTilesTileType::Avif,
+            _ => PMTilesTileType::Unknown,
+        }
+    }
+
+    /// The source type and extension of the tiles. `None` if the tile type is unknown
+    pub fn source(self) -> Option<(SourceType, &'static str)> {
+        match self {
+            PMTilesTileType::Unknown => None,
+            PMTilesTileType::Mvt => Some((SourceType::Vector, "pbf")),
+            PMTilesTileType::Png => Some((SourceType::Raster, "png")),
+            PMTilesTileType::Jpeg => Some((SourceType::Raster, "jpg")),
+            PMTilesTileType::Webp => Some((SourceType::Raster, "webp")),
+            PMTilesTileType::Avif => Some((SourceType::Raster, "avif")),
+        }
+    }
+}
+
+/// # PMTiles Header
+///
+/// ## Description
+/// The fixed size header of a [PMTiles v3](https://github.com/protomaps/PMTiles/blob/main/spec/v3/spec.md)
+/// archive. Positions are stored as E7 integers (degrees * 10,000,000).
+///
+/// ## Usage
+/// - [`PMTilesHeader::from_bytes`]: Parse the first [`PMTILES_HEADER_SIZE`] bytes of an archive
+/// - [`PMTilesHeader::to_bytes`]: Serialize the header
+/// - [`Metadata::to_pmtiles_header`]: Build a header from a Metadata
+/// - [`Metadata::from_pmtiles`]: Build a Metadata from a header and its JSON metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PMTilesHeader {
+    /// Spec version. Always 3
+    pub version: u8,
+    /// Offset of the root directory
+    pub root_directory_offset: u64,
+    /// Length of the root directory
+    pub root_directory_length: u64,
+    /// Offset of the JSON metadata
+    pub metadata_offset: u64,
+    /// Length of the JSON metadata
+    pub metadata_length: u64,
+    /// Offset of the leaf directories
+    pub leaf_directories_offset: u64,
+    /// Length of the leaf directories
+    pub leaf_directories_length: u64,
+    /// Offset of the tile data
+    pub tile_data_offset: u64,
+    /// Length of the tile data
+    pub tile_data_length: u64,
+    /// Number of addressed tiles
+    pub addressed_tiles_count: u64,
+    /// Number of tile entries
+    pub tile_entries_count: u64,
+    /// Number of unique tile contents
+    pub tile_contents_count: u64,
+    /// Whether the tile data is ordered by tile id
+    pub clustered: bool,
+    /// Compression of the directories and JSON metadata
+    pub internal_compression: PMTilesCompression,
+    /// Compression of the tiles
+    pub tile_compression: PMTilesCompression,
+    /// Type of the tiles
+    pub tile_type: PMTilesTileType,
+    /// Minimum zoom of the tiles
+    pub min_zoom: u8,
+    /// Maximum zoom of the tiles
+    pub max_zoom: u8,
+    /// Minimum longitude of the bounds as E7
+    pub min_lon_e7: i32,
+    /// Minimum latitude of the bounds as E7
+    pub min_lat_e7: i32,
+    /// Maximum longitude of the bounds as E7
+    pub max_lon_e7: i32,
+    /// Maximum latitude of the bounds as E7
+    pub max_lat_e7: i32,
+    /// Zoom of the center
+    pub center_zoom: u8,
+    /// Longitude of the center as E7
+    pub center_lon_e7: i32,
+    /// Latitude of the center as E7
+    pub center_lat_e7: i32,
+}
+impl Default for PMTilesHeader {
+    fn default() -> Self {
+        PMTilesHeader {
+            version: 3,
+            root_directory_offset: 0,
+            root_directory_length: 0,
+            metadata_offset: 0,
+            metadata_length: 0,
+            leaf_directories_offset: 0,
+            leaf_directories_length: 0,
+            tile_data_offset: 0,
+            tile_data_length: 0,
+            addressed_tiles_count: 0,
+            tile_entries_count: 0,
+            tile_contents_count: 0,
+            clustered: false,
+            internal_compression: PMTilesCompression::default(),
+            tile_compression: PMTilesCompression::default(),
+            tile_type: PMTilesTileType::default(),
+            min_zoom: 0,
+            max_zoom: 0,
+            min_lon_e7: 0,
+            min_lat_e7: 0,
+            max_lon_e7: 0,
+            max_lat_e7: 0,
+            center_zoom: 0,
+            center_lon_e7: 0,
+            center_lat_e7: 0,
+        }
+    }
+}
+impl PMTilesHeader {
+    /// Parse a header from the start of an archive. Extra bytes are ignored
+    pub fn from_bytes(bytes: &[u8]) -> Result<PMTilesHeader, PMTilesError> {
+        if bytes.len() < PMTILES_HEADER_SIZE {
+            return Err(PMTilesError::TooShort(bytes.len()));
+        }
+        if &bytes[0..7] != PMTILES_MAGIC {
+            return Err(PMTilesError::InvalidMagic);
+        }
+        if bytes[7] != 3 {
+            return Err(PMTilesError::UnsupportedVersion(bytes[7]));
+        }
+        let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        let i32_at = |at: usize| i32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        Ok(PMTilesHeader {
+            version: bytes[7],
+            root_directory_offset: u64_at(8),
+            root_directory_length: u64_at(16),
+            metadata_offset: u64_at(24),
+            metadata_length: u64_at(32),
+            leaf_directories_offset: u64_at(40),
+            leaf_directories_length: u64_at(48),
+            tile_data_offset: u64_at(56),
+            tile_data_length: u64_at(64),
+            addressed_tiles_count: u64_at(72),
+            tile_entries_count: u64_at(80),
+            tile_contents_count: u64_at(88),
+            clustered: bytes[96] == 1,
+            internal_compression: bytes[97].into(),
+            tile_compression: bytes[98].into(),
+            tile_type: bytes[99].into(),
+            min_zoom: bytes[100],
+            max_zoom: bytes[101],
+            min_lon_e7: i32_at(102),
+            min_lat_e7: i32_at(106),
+            max_lon_e7: i32_at(110),
+            max_lat_e7: i32_at(114),
+            center_zoom: bytes[118],
+            center_lon_e7: i32_at(119),
+            center_lat_e7: i32_at(123),
+        })
+    }
+
+    /// Serialize the header into its [`PMTILES_HEADER_SIZE`] bytes
+    pub fn to_bytes(&self) -> [u8; PMTILES_HEADER_SIZE] {
+        let mut bytes = [0; PMTILES_HEADER_SIZE];
+        bytes[0..7].copy_from_slice(PMTILES_MAGIC);
+        bytes[7] = self.version;
+        let u64s = [
+            self.root_directory_offset,
+            self.root_directory_length,
+            self.metadata_offset,
+            self.metadata_length,
+            self.leaf_directories_offset,
+            self.leaf_directories_length,
+            self.tile_data_offset,
+            self.tile_data_length,
+            self.addressed_tiles_count,
+            self.tile_entries_count,
+            self.tile_contents_count,
+        ];
+        for (i, value) in u64s.iter().enumerate() {
+            bytes[8 + i * 8..16 + i * 8].copy_from_slice(&value.to_le_bytes());
+        }
+        bytes[96] = self.clustered as u8;
+        bytes[97] = self.internal_compression as u8;
+        bytes[98] = self.tile_compression as u8;
+        bytes[99] = self.tile_type as u8;
+        bytes[100] = self.min_zoom;
+        bytes[101] = self.max_zoom;
+        let bounds = [self.min_lon_e7, self.min_lat_e7, self.max_lon_e7, self.max_lat_e7];
+        for (i, value) in bounds.iter().enumerate() {
+            bytes[102 + i * 4..106 + i * 4].copy_from_slice(&value.to_le_bytes());
+        }
+        bytes[118] = self.center_zoom;
+        bytes[119..123].copy_from_slice(&self.center_lon_e7.to_le_bytes());
+        bytes[123..127].copy_from_slice(&self.center_lat_e7.to_le_bytes());
+        bytes
+    }
+
+    /// The bounds in degrees
+    pub fn bounds(&self) -> BBox {
+        BBox::new(
+            from_e7(self.min_lon_e7),
+            from_e7(self.min_lat_e7),
+            from_e7(self.max_lon_e7),
+            from_e7(self.max_lat_e7),
+        )
+    }
+
+    /// The center in degrees
+    pub fn center(&self) -> Center {
+        Center {
+            lon: from_e7(self.center_lon_e7),
+            lat: from_e7(self.center_lat_e7),
+            zoom: self.center_zoom,
+        }
+    }
+}
+
+impl Metadata {
+    /// Build a PMTiles header from the zooms, bounds, center, encoding and tile type.
+    ///
+    /// The offsets, lengths and counts describe the archive layout so they are left at zero
+    /// for the writer to fill in. The internal compression defaults to gzip. PMTiles only stores
+    /// Web Mercator tiles so S2 specific data is not represented.
+    pub fn to_pmtiles_header(&self) -> PMTilesHeader {
+        PMTilesHeader {
+            internal_compression: PMTilesCompression::Gzip,
+            tile_compression: self.encoding.into(),
+            tile_type: PMTilesTileType::from_metadata(self.r#type, &self.extension),
+            min_zoom: self.minzoom,
+            max_zoom: self.maxzoom,
+            min_lon_e7: to_e7(self.bounds.left),
+            min_lat_e7: to_e7(self.bounds.bottom),
+            max_lon_e7: to_e7(self.bounds.right),
+            max_lat_e7: to_e7(self.bounds.top),
+            center_zoom: self.centerpoint.zoom,
+            center_lon_e7: to_e7(self.centerpoint.lon),
+            center_lat_e7: to_e7(self.centerpoint.lat),
+            ..Default::default()
+        }
+    }
+
+    /// Build a Metadata from a PMTiles header and the archive's (decompressed) JSON metadata.
+    ///
+    /// The JSON metadata is read as S2 metadata when it has a `s2tilejson` key, otherwise as
+    /// legacy TileJSON like tippecanoe writes. The zooms, bounds, center, encoding and tile type
+    /// of the header take precedence over the JSON metadata. Legacy TileJSON has no tile bounds,
+    /// so `wmbounds` is filled from the header bounds and zooms.
+    pub fn from_pmtiles(
+        header: &PMTilesHeader,
+        json: Option<&str>,
+    ) -> Result<Metadata, PMTilesError> {
+        let json = json.map(str::trim).filter(|j| !j.is_empty()).unwrap_or("{}");
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(PMTilesError::InvalidJson)?;
+        let legacy = value.get("s2tilejson").is_none();
+        let mut meta: Metadata = if !legacy {
+            serde_json::from_value(value).map_err(PMTilesError::InvalidJson)?
+        } else {
+            let mut mapbox: MapboxTileJSONMetadata =
+                serde_json::from_value(value).map_err(PMTilesError::InvalidJson)?;
+            mapbox.minzoom = Some(header.min_zoom);
+            mapbox.maxzoom = Some(header.max_zoom);
+            Metadata { scheme: mapbox.scheme.unwrap_or(Scheme::Xyz), ..mapbox.to_metadata() }
+        };
+        if meta.tiles.as_ref().is_some_and(Vec::is_empty) {
+            meta.tiles = None;
+        }
+        meta.minzoom = header.min_zoom;
+        meta.maxzoom = header.max_zoom;
+        meta.bounds = header.bounds();
+        meta.centerpoint = header.center();
+        if meta.center.is_some() {
+            let center = header.center();
+            meta.center = Some([center.lon, center.lat, center.zoom as f64]);
+        }
+        if let Some(encoding) = header.tile_compression.encoding() {
+            meta.encoding = encoding;
+        }
+        if let Some((source_type, extension)) = header.tile_type.source() {
+            meta.r#type = source_type;
+            meta.extension = String::from(extension);
+        }
+        if legacy {
+            meta.fill_tile_bounds();
+        }
+        Ok(meta)
+    }
+}
+
+/// Degrees to E7, rounded to the nearest integer
+fn to_e7(degrees: f64) -> i32 {
+    let e7 = degrees * 10_000_000.;
+    if e7 < 0. { (e7 - 0.5) as i32 } else { (e7 + 0.5) as i32 }
+}
+
+/// E7 to degrees
+fn from_e7(e7: i32) -> f64 {
+    e7 as f64 / 10_000_000.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrawType, Face};
+    use alloc::{string::ToString, vec};
+
+    fn header() -> PMTilesHeader {
+        PMTilesHeader {
+            root_directory_offset: 127,
+            root_directory_length: 25,
+            metadata_offset: 152,
+            metadata_length: 247,
+            leaf_directories_offset: 399,
+            tile_data_offset: 399,
+            tile_data_length: 69,
+            addressed_tiles_count: 1,
+            tile_entries_count: 1,
+            tile_contents_count: 1,
+            clustered: true,
+            internal_compression: PMTilesCompression::Gzip,
+            tile_compression: PMTilesCompression::Gzip,
+            tile_type: PMTilesTileType::Mvt,
+            min_zoom: 0,
+            max_zoom: 14,
+            min_lon_e7: -1_800_000_000,
+            min_lat_e7: -850_511_287,
+            max_lon_e7: 1_800_000_000,
+            max_lat_e7: 850_511_287,
+            center_zoom: 3,
+            center_lon_e7: 123_456_789,
+            center_lat_e7: -98_765_432,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_header_bytes() {
+        let bytes = header().to_bytes();
+        assert_eq!(&bytes[0..8], b"PMTiles\x03");
+        assert_eq!(&bytes[8..16], &127u64.to_le_bytes());
+        assert_eq!(&bytes[96..102], &[1, 2, 2, 1, 0, 14]);
+        assert_eq!(&bytes[102..106], &(-1_800_000_000i32).to_le_bytes());
+        assert_eq!(bytes[118], 3);
+        assert_eq!(&bytes[123..127], &(-98_765_432i32).to_le_bytes());
+        assert_eq!(PMTilesHeader::from_bytes(&bytes).unwrap(), header());
+
+        // trailing archive bytes are ignored
+        let mut archive = bytes.to_vec();
+        archive.extend_from_slice(&[0x1f, 0x8b]);
+        assert_eq!(PMTilesHeader::from_bytes(&archive).unwrap(), header());
+
+        let err = PMTilesHeader::from_bytes(&bytes[..100]).unwrap_err();
+        assert_eq!(err.to_string(), "PMTiles header needs 127 bytes, got 100");
+        let mut bad = bytes;
+        bad[0] = b'X';
+        assert!(matches!(PMTilesHeader::from_bytes(&bad), Err(PMTilesError::InvalidMagic)));
+        let mut bad = bytes;
+        bad[7] = 2;
+        assert!(matches!(
+            PMTilesHeader::from_bytes(&bad),
+            Err(PMTilesError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn test_from_pmtiles() {
+        let json = r#"{
+            "name": "roads",
+            "vector_layers": [{ "id": "roads", "fields": { "name": "String" } }],
+            "tilestats": {
+                "layerCount": 1,
+                "layers": [{ "layer": "roads", "count": 1, "geometry": "LineString" }]
+            }
+        }"#;
+        let meta = Metadata::from_pmtiles(&header(), Some(json)).unwrap();
+        assert_eq!(meta.name, "roads");
+        assert_eq!(meta.scheme, Scheme::Xyz);
+        assert_eq!(meta.faces, vec![Face::Face0]);
+        assert_eq!((meta.minzoom, meta.maxzoom), (0, 14));
+        assert_eq!(meta.bounds, BBox::new(-180., -85.0511287, 180., 85.0511287));
+        assert_eq!(meta.centerpoint, Center { lon: 12.3456789, lat: -9.8765432, zoom: 3 });
+        assert_eq!(meta.encoding, Encoding::Gzip);
+        assert_eq!((meta.r#type, meta.extension.as_str()), (SourceType::Vector, "pbf"));
+        assert_eq!(meta.tiles, None);
+        assert_eq!(meta.layers["roads"].draw_types, vec![DrawType::Lines]);
+        assert_eq!(meta.layers["roads"].maxzoom, 14);
+        assert_eq!(meta.wmbounds[&3], BBox::new(0, 0, 7, 7));
+
+        // the tile bounds follow the header even if the json has its own
+        let small = PMTilesHeader {
+            min_lon_e7: 100_000_000,
+            min_lat_e7: 100_000_000,
+            max_lon_e7: 200_000_000,
+            max_lat_e7: 200_000_000,
+            ..header()
+        };
+        let json = r#"{ "tilejson": "3.0.0", "bounds": [-180, -85, 180, 85], "maxzoom": 2 }"#;
+        let meta = Metadata::from_pmtiles(&small, Some(json)).unwrap();
+        assert!(meta.has_tile_wm(3, 4, 3));
+        assert!(!meta.has_tile_wm(3, 0, 0));
+        assert_eq!(meta.wmbounds[&14].left, 8647);
+        let meta = Metadata::from_pmtiles(&small, None).unwrap();
+        assert!(meta.has_tile_wm(3, 4, 3));
+        assert!(!meta.has_tile_wm(3, 0, 0));
+
+        let raster = PMTilesHeader {
+            tile_type: PMTilesTileType::Webp,
+            tile_compression: PMTilesCompression::None,
+            ..header()
+        };
+        let meta = Metadata::from_pmtiles(&raster, None).unwrap();
+        assert_eq!((meta.r#type, meta.extension.as_str()), (SourceType::Raster, "webp"));
+        assert_eq!(meta.encoding, Encoding::None);
+
+        assert!(matches!(
+            Metadata::from_pmtiles(&header(), Some("[1]")),
+            Err(PMTilesError::InvalidJson(_))
+        ));
+    }
+
+    #[test]
+    fn test_to_pmtiles_header() {
+        let meta = Metadata::from_pmtiles(&header(), None).unwrap();
+        let expected = PMTilesHeader {
+            internal_compression: PMTilesCompression::Gzip,
+            tile_compression: PMTilesCompression::Gzip,
+            tile_type: PMTilesTileType::Mvt,
+            ..header()
+        };
+        let header = meta.to_pmtiles_header();
+        assert_eq!(header.root_directory_offset, 0);
+        assert_eq!(header.tile_type, expected.tile_type);
+        assert_eq!((header.min_zoom, header.max_zoom), (0, 14));
+        assert_eq!(header.bounds(), expected.bounds());
+        assert_eq!(header.center(), expected.center());
+        assert!(!header.clustered);
+
+        let meta = Metadata {
+            r#type: SourceType::Raster,
+            extension: "jpeg".into(),
+            encoding: Encoding::Zstd,
+            bounds: BBox::new(-0.00000004, -0.00000006, 0.00000006, 0.00000004),
+            ..Default::default()
+        };
+        let header = meta.to_pmtiles_header();
+        assert_eq!(header.tile_type, PMTilesTileType::Jpeg);
+        assert_eq!(header.tile_compression, PMTilesCompression::Zstd);
+        assert_eq!(
+            [header.min_lon_e7, header.min_lat_e7, header.max_lon_e7, header.max_lat_e7],
+            [0, -1, 1, 0]
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{BBox, Face, LonLatBounds};
+use core::f64::consts::PI;
+use libm::{atan, atan2, cos, log, sin, sinh, sqrt, tan};
+
+/// The latitude limit of the Web Mercator projection
+pub(crate) const MAX_LAT_WM: f64 = 85.0511287798066;
+
+/// The fractional Web Mercator tile column of a longitude at a zoom
+pub(crate) fn lon_to_wm_x(lon: f64, zoom: u8) -> f64 {
+    (lon + 180.) / 360. * (1u64 << zoom) as f64
+}
+
+/// The fractional Web Mercator tile row (counted from the north) of a latitude at a zoom
+pub(crate) fn lat_to_wm_y(lat: f64, zoom: u8) -> f64 {
+    let lat = lat.clamp(-MAX_LAT_WM, MAX_LAT_WM).to_radians();
+    (1. - log(tan(lat) + 1. / cos(lat)) / PI) / 2. * (1u64 << zoom) as f64
+}
+
+/// The index of the tile holding a fractional tile coordinate, clamped to the tiles of the zoom
+pub(crate) fn tile_index(coord: f64, zoom: u8) -> u64 {
+    (coord as u64).min((1u64 << zoom) - 1)
+}
+
+/// The lon-lat bounds of a Web Mercator tile. `y` is counted from the north
+pub(crate) fn wm_tile_lon_lat_bounds(zoom: u8, x: u64, y: u64) -> LonLatBounds {
+    let size = (1u64 << zoom) as f64;
+    let lon = |x: u64| x as f64 / size * 360. - 180.;
+    let lat = |y: u64| atan(sinh(PI * (1. - 2. * y as f64 / size))).to_degrees();
+    BBox::new(lon(x), lat(y + 1), lon(x + 1), lat(y))
+}
+
+/// The lon-lat bounds of an S2 tile.
+///
+/// The edges of a tile are great circle arcs, so the longitude only peaks at the corners while
+/// the latitude may peak halfway along an edge. Tiles holding a pole or crossing the antimeridian
+/// span every longitude.
+pub(crate) fn s2_tile_lon_lat_bounds(face: Face, zoom: u8, x: u64, y: u64) -> LonLatBounds {
+    let size = (1u64 << zoom) as f64;
+    let (u0, u1) = (st_to_uv(x as f64 / size), st_to_uv((x + 1) as f64 / size));
+    let (v0, v1) = (st_to_uv(y as f64 / size), st_to_uv((y + 1) as f64 / size));
+    let corners = [(u0, v0), (u1, v0), (u1, v1), (u0, v1)].map(|(u, v)| face_uv_to_xyz(face, u, v));
+
+    let mut bottom = f64::MAX;
+    let mut top = f64::MIN;
+    let mut crosses_antimeridian = false;
+    for (i, a) in corners.iter().enumerate() {
+        let b = &corners[(i + 1) % 4];
+        let d = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+        let mut extend = |p: [f64; 3]| {
+            let (_, lat) = xyz_to_lon_lat(p);
+            (bottom, top) = (bottom.min(lat), top.max(lat));
+        };
+        extend(*a);
+        // the latitude along the edge `a + t * d` peaks where its derivative is zero
+        let (a_dot_d, a_dot_a, d_dot_d) = (dot(a, &d), dot(a, a), dot(&d, &d));
+        let t = (a[2] * a_dot_d - d[2] * a_dot_a) / (d[2] * a_dot_d - a[2] * d_dot_d);
+        if t > 0. && t < 1. {
+            extend([a[0] + t * d[0], a[1] + t * d[1], a[2] + t * d[2]]);
+        }
+        // the edge crosses the antimeridian where `y` changes sign with a negative `x`
+        if (a[1] < 0.) != (b[1] < 0.) && a[1] != 0. && b[1] != 0. {
+            let t = a[1] / (a[1] - b[1]);
+            crosses_antimeridian |= a[0] + t * d[0] < 0.;
+        }
+    }
+
+    let holds_pole =
+        matches!(face, Face::Face2 | Face::Face5) && u0 <= 0. && u1 >= 0. && v0 <= 0. && v1 >= 0.;
+    if holds_pole {
+        (bottom, top) = if face == Face::Face2 { (bottom, 90.) } else { (-90., top) };
+    }
+    if holds_pole || crosses_antimeridian {
+        return BBox::new(-180., bottom, 180., top);
+    }
+
+    // corners on the antimeridian take the side of the rest of the tile
+    let east = corners.iter().any(|c| c[1] > 0.);
+    let lons = corners.map(|c| match c[1] == 0. && c[0] < 0. {
+        true if east => 180.,
+        true => -180.,
+        false => xyz_to_lon_lat(c).0,
+    });
+    let left = lons.iter().copied().fold(f64::MAX, f64::min);
+    let right = lons.iter().copied().fold(f64::MIN, f64::max);
+    BBox::new(left, bottom, right, top)
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// The unit vector of a longitude and latitude in degrees
+pub(crate) fn lon_lat_to_xyz(lon: f64, lat: f64) -> [f64; 3] {
+    let (lon, lat) = (lon.to_radians(), lat.to_radians());
+    [cos(lat) * cos(lon), cos(lat) * sin(lon), sin(lat)]
+}
+
+/// The longitude and latitude in degrees of a point
+pub(crate) fn xyz_to_lon_lat([x, y, z]: [f64; 3]) -> (f64, f64) {
+    (atan2(y, x).to_degrees(), atan2(z, sqrt(x * x + y * y)).to_degrees())
+}
+
+/// The `u` and `v` of a point on a face, or `None` if the point is not on the face
+pub(crate) fn xyz_to_face_uv(face: Face, [x, y, z]: [f64; 3]) -> Option<(f64, f64)> {
+    let axis = match face {
+        Face::Face0 => x,
+        Face::Face1 => y,
+        Face::Face2 => z,
+        Face::Face3 => -x,
+        Face::Face4 => -y,
+        Face::Face5 => -z,
+    };
+    if axis <= 0. {
+        return None;
+    }
+    let (u, v) = match face {
+        Face::Face0 => (y / x, z / x),
+        Face::Face1 => (-x / y, z / y),
+        Face::Face2 => (-x / z, -y / z),
+        Face::Face3 => (z / x, y / x),
+        Face::Face4 => (z / y, -x / y),
+        Face::Face5 => (-y / z, -x / z),
+    };
+    // allow for the rounding of points on the edge of the face
+    const LIMIT: f64 = 1. + 1e-12;
+    (u.abs() <= LIMIT && v.abs() <= LIMIT).then_some((u.clamp(-1., 1.), v.clamp(-1., 1.)))
+}
+
+/// The point of a `u` and `v` on a face. Not normalized
+pub(crate) fn face_uv_to_xyz(face: Face, u: f64, v: f64) -> [f64; 3] {
+    match face {
+        Face::Face0 => [1., u, v],
+        Face::Face1 => [-u, 1., v],
+        Face::Face2 => [-u, -v, 1.],
+        Face::Face3 => [-1., -v, -u],
+        Face::Face4 => [v, -1., -u],
+        Face::Face5 => [v, u, -1.],
+    }
+}
+
+/// The quadratic projection of a face `u` or `v` into `s` or `t`
+pub(crate) fn uv_to_st(u: f64) -> f64 {
+    if u >= 0. { 0.5 * sqrt(1. + 3. * u) } else { 1. - 0.5 * sqrt(1. - 3. * u) }
+}
+
+/// The inverse of [`uv_to_st`]
+pub(crate) fn st_to_uv(s: f64) -> f64 {
+    if s >= 0.5 { (4. * s * s - 1.) / 3. } else { (1. - 4. * (1. - s) * (1. - s)) / 3. }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FACES;
+
+    #[test]
+    fn test_face_uv() {
+        for face in FACES {
+            for (u, v) in [(0., 0.), (0.5, -0.25), (-1., 1.)] {
+                let (lon, lat) = xyz_to_lon_lat(face_uv_to_xyz(face, u, v));
+                let (u2, v2) = xyz_to_face_uv(face, lon_lat_to_xyz(lon, lat)).unwrap();
+                assert!((u - u2).abs() < 1e-12 && (v - v2).abs() < 1e-12);
+            }
+        }
+        assert_eq!(xyz_to_lon_lat(face_uv_to_xyz(Face::Face2, 0., 0.)).1, 90.);
+        assert_eq!(xyz_to_face_uv(Face::Face3, lon_lat_to_xyz(0., 0.)), None);
+        assert_eq!((uv_to_st(-1.), uv_to_st(0.), uv_to_st(1.)), (0., 0.5, 1.));
+        assert_eq!((st_to_uv(0.), st_to_uv(0.5), st_to_uv(1.)), (-1., 0., 1.));
+        assert!((st_to_uv(uv_to_st(0.3)) - 0.3).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_wm() {
+        assert_eq!(lon_to_wm_x(-180., 2), 0.);
+        assert_eq!(lon_to_wm_x(0., 2), 2.);
+        assert!((lat_to_wm_y(MAX_LAT_WM, 2)).abs() < 1e-9);
+        assert!((lat_to_wm_y(-90., 2) - 4.).abs() < 1e-9);
+        assert_eq!(lat_to_wm_y(0., 2), 2.);
+        assert_eq!((tile_index(4., 2), tile_index(-1., 2), tile_index(2.5, 2)), (3, 0, 2));
+    }
+
+    #[test]
+    fn test_wm_tile_lon_lat_bounds() {
+        let world = wm_tile_lon_lat_bounds(0, 0, 0);
+        assert_eq!((world.left, world.right), (-180., 180.));
+        assert!((world.top - MAX_LAT_WM).abs() < 1e-9 && (world.bottom + MAX_LAT_WM).abs() < 1e-9);
+        let tile = wm_tile_lon_lat_bounds(1, 1, 1);
+        assert_eq!((tile.left, tile.bottom, tile.right), (0., -MAX_LAT_WM, 180.));
+        assert!(tile.top.abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_s2_tile_lon_lat_bounds() {
+        let close = |a: LonLatBounds, b: LonLatBounds| {
+            let d = [a.left - b.left, a.bottom - b.bottom, a.right - b.right, a.top - b.top];
+            assert!(d.iter().all(|d| d.abs() < 1e-9), "{a:?} != {b:?}");
+        };
+        // the corners of face 0 are at 45 degrees longitude, and its edges bulge to 45 degrees
+        // latitude halfway
+        close(s2_tile_lon_lat_bounds(Face::Face0, 0, 0, 0), BBox::new(-45., -45., 45., 45.));
+        close(s2_tile_lon_lat_bounds(Face::Face0, 1, 1, 1), BBox::new(0., 0., 45., 45.));
+        // the corners of face 2 reach down to 35.26 degrees latitude
+        let corner = atan(1. / sqrt(2.)).to_degrees();
+        close(s2_tile_lon_lat_bounds(Face::Face2, 0, 0, 0), BBox::new(-180., corner, 180., 90.));
+        // every zoom 1 tile of a polar face touches the pole
+        close(s2_tile_lon_lat_bounds(Face::Face5, 1, 0, 1), BBox::new(-180., -90., 180., -corner));
+        // a tile of face 2 away from the pole
+        let tile = s2_tile_lon_lat_bounds(Face::Face2, 2, 3, 1);
+        assert!(tile.top < 90. && tile.right - tile.left < 90.);
+        // face 3 is centered on the antimeridian
+        close(s2_tile_lon_lat_bounds(Face::Face3, 0, 0, 0), BBox::new(-180., -45., 180., 45.));
+        close(s2_tile_lon_lat_bounds(Face::Face3, 1, 0, 0), BBox::new(135., 0., 180., 45.));
+        close(s2_tile_lon_lat_bounds(Face::Face3, 1, 1, 1), BBox::new(-180., -45., -135., 0.));
+    }
+}
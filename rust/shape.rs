@@ -0,0 +1,412 @@
+use crate::{
+    LayerMetaData, PrimitiveShape, PrimitiveShapeType, Shape, ShapeType, Value, ValueType,
+};
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+
+/// Two shapes describe the same key with types that can't be reconciled,
+/// e.g. a `string` and a nested object
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeConflict {
+    /// Dotted path of the conflicting key, e.g. `info.name`
+    pub path: String,
+    /// The type found in the first shape
+    pub ours: ShapeType,
+    /// The type found in the second shape
+    pub theirs: ShapeType,
+}
+impl ShapeConflict {
+    pub(crate) fn prefixed(self, prefix: &str) -> Self {
+        ShapeConflict { path: format!("{prefix}.{}", self.path), ..self }
+    }
+}
+impl fmt::Display for ShapeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = |t: &ShapeType| serde_json::to_string(t).unwrap_or_default();
+        write!(
+            f,
+            "incompatible shape types at {}: {} and {}",
+            self.path,
+            json(&self.ours),
+            json(&self.theirs)
+        )
+    }
+}
+
+impl LayerMetaData {
+    /// Union another description of the same layer into this one.
+    ///
+    /// The draw types are unioned, the zoom range is widened and `shape`/`mShape` are unioned
+    /// with [`shape_union`]. The description is only taken from `other` if this layer has none.
+    /// On a conflict the layer is left untouched and the path of the error is prefixed with
+    /// `shape.` or `mShape.`.
+    pub fn union(&mut self, other: &LayerMetaData) -> Result<(), ShapeConflict> {
+        let shape = shape_union(&self.shape, &other.shape).map_err(|e| e.prefixed("shape"))?;
+        let m_shape = match (&self.m_shape, &other.m_shape) {
+            (Some(ours), Some(theirs)) => {
+                Some(shape_union(ours, theirs).map_err(|e| e.prefixed("mShape"))?)
+            }
+            (ours, theirs) => ours.clone().or_else(|| theirs.clone()),
+        };
+
+        self.shape = shape;
+        self.m_shape = m_shape;
+        if self.description.is_none() {
+            self.description = other.description.clone();
+        }
+        self.minzoom = self.minzoom.min(other.minzoom);
+        self.maxzoom = self.maxzoom.max(other.maxzoom);
+        for draw_type in &other.draw_types {
+            if !self.draw_types.contains(draw_type) {
+                self.draw_types.push(*draw_type);
+            }
+        }
+        self.draw_types.sort();
+        Ok(())
+    }
+}
+
+/// Union two shapes into one that describes the data of both.
+///
+/// Keys missing from either shape are added, nested objects and arrays are merged and numeric
+/// types are widened (`u64` + `i64` -> `i64`, any integer + float -> `f64`, `f32` + `f64` ->
+/// `f64`). A `null` is treated as an optional value and takes the type of the other side.
+/// Returns a [`ShapeConflict`] if a key can't be reconciled, e.g. `string` and `bool` or
+/// `string` and a nested object.
+///
+/// ```
+/// use s2_tilejson::{Shape, shape_union};
+///
+/// let a: Shape = serde_json::from_str(r#"{ "id": "i64", "name": "string" }"#).unwrap();
+/// let b: Shape = serde_json::from_str(r#"{ "id": "f64", "rank": "u64" }"#).unwrap();
+/// let expected: Shape =
+///     serde_json::from_str(r#"{ "id": "f64", "name": "string", "rank": "u64" }"#).unwrap();
+/// assert_eq!(shape_union(&a, &b), Ok(expected));
+/// ```
+pub fn shape_union(ours: &Shape, theirs: &Shape) -> Result<Shape, ShapeConflict> {
+    let mut res = ours.clone();
+    union_into(&mut res, theirs, "")?;
+    Ok(res)
+}
+
+/// Infer the [`Shape`] of a properties or M-Values object.
+///
+/// Unlike `Shape::from`, arrays mixing incompatible types return a [`ShapeConflict`] instead of
+/// panicking. Numbers in arrays are widened like [`shape_union`].
+pub fn shape_from_value(value: &Value) -> Result<Shape, ShapeConflict> {
+    shape_from_value_at(value, "")
+}
+
+fn shape_from_value_at(value: &Value, prefix: &str) -> Result<Shape, ShapeConflict> {
+    let mut shape = Shape::new();
+    for (key, value) in value.iter() {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+        let shape_type = match value {
+            ValueType::Primitive(prim) => ShapeType::Primitive(prim.into()),
+            ValueType::Nested(nested) => ShapeType::Nested(shape_from_value_at(nested, &path)?),
+            ValueType::Array(items) => {
+                let mut element: Option<PrimitiveShapeType> = None;
+                for item in items {
+                    let item = PrimitiveShapeType::from(item);
+                    element = Some(match element {
+                        None => item,
+                        Some(prev) => union_element(&prev, &item).ok_or_else(|| ShapeConflict {
+                            path: path.clone(),
+                            ours: ShapeType::Array(Vec::from([prev])),
+                            theirs: ShapeType::Array(Vec::from([item])),
+                        })?,
+                    });
+                }
+                ShapeType::Array(element.into_iter().collect())
+            }
+        };
+        shape.insert(key.clone(), shape_type);
+    }
+    Ok(shape)
+}
+
+fn union_into(ours: &mut Shape, theirs: &Shape, prefix: &str) -> Result<(), ShapeConflict> {
+    for (key, theirs) in theirs.iter() {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+        let merged = match ours.get(key) {
+            None => theirs.clone(),
+            Some(ours) => union_type(ours, theirs, &path)?,
+        };
+        ours.insert(key.clone(), merged);
+    }
+    Ok(())
+}
+
+fn union_type(
+    ours: &ShapeType,
+    theirs: &ShapeType,
+    path: &str,
+) -> Result<ShapeType, ShapeConflict> {
+    let conflict =
+        || ShapeConflict { path: path.into(), ours: ours.clone(), theirs: theirs.clone() };
+    match (ours, theirs) {
+        (ShapeType::Primitive(a), ShapeType::Primitive(b)) => {
+            union_primitive(a, b).map(ShapeType::Primitive).ok_or_else(conflict)
+        }
+        (ShapeType::Primitive(PrimitiveShape::Null), other)
+        | (other, ShapeType::Primitive(PrimitiveShape::Null)) => Ok(other.clone()),
+        (ShapeType::Nested(a), ShapeType::Nested(b)) => {
+            let mut nested = a.clone();
+            union_into(&mut nested, b, path)?;
+            Ok(ShapeType::Nested(nested))
+        }
+        (ShapeType::Array(a), ShapeType::Array(b)) => {
+            union_array(a, b).map(ShapeType::Array).ok_or_else(conflict)
+        }
+        _ => Err(conflict()),
+    }
+}
+
+/// Arrays hold a single element type, so the element types are unioned
+fn union_array(
+    a: &[PrimitiveShapeType],
+    b: &[PrimitiveShapeType],
+) -> Option<Vec<PrimitiveShapeType>> {
+    let element = match (a.first(), b.first()) {
+        (None, _) => return Some(b.to_vec()),
+        (_, None) => return Some(a.to_vec()),
+        (Some(a), Some(b)) => union_element(a, b)?,
+    };
+    Some(Vec::from([element]))
+}
+
+fn union_element(a: &PrimitiveShapeType, b: &PrimitiveShapeType) -> Option<PrimitiveShapeType> {
+    match (a, b) {
+        (PrimitiveShapeType::Primitive(a), PrimitiveShapeType::Primitive(b)) => {
+            union_primitive(a, b).map(PrimitiveShapeType::Primitive)
+        }
+        (PrimitiveShapeType::NestedPrimitive(a), PrimitiveShapeType::NestedPrimitive(b)) => {
+            let mut nested = a.clone();
+            for (key, b) in b.iter() {
+                let merged = match nested.get(key) {
+                    None => b.clone(),
+                    Some(a) => union_primitive(a, b)?,
+                };
+                nested.insert(key.clone(), merged);
+            }
+            Some(PrimitiveShapeType::NestedPrimitive(nested))
+        }
+        (PrimitiveShapeType::Primitive(PrimitiveShape::Null), other)
+        | (other, PrimitiveShapeType::Primitive(PrimitiveShape::Null)) => Some(other.clone()),
+        _ => None,
+    }
+}
+
+/// Union two primitives, widening numbers. Returns `None` if they are incompatible
+fn union_primitive(a: &PrimitiveShape, b: &PrimitiveShape) -> Option<PrimitiveShape> {
+    use PrimitiveShape::*;
+    match (a, b) {
+        _ if a == b => Some(a.clone()),
+        (Null, other) | (other, Null) => Some(other.clone()),
+        (U64 | I64, U64 | I64) => Some(I64),
+        (U64 | I64 | F32 | F64, U64 | I64 | F32 | F64) => Some(F64),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrawType, MetadataBuilder, VectorLayer};
+    use alloc::{collections::BTreeMap, string::ToString};
+
+    fn shape(json: &str) -> Shape {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_union_primitives() {
+        use PrimitiveShape::*;
+        assert_eq!(union_primitive(&String, &String), Some(String));
+        assert_eq!(union_primitive(&U64, &I64), Some(I64));
+        assert_eq!(union_primitive(&I64, &F64), Some(F64));
+        assert_eq!(union_primitive(&U64, &F32), Some(F64));
+        assert_eq!(union_primitive(&F32, &F64), Some(F64));
+        assert_eq!(union_primitive(&F32, &F32), Some(F32));
+        assert_eq!(union_primitive(&Null, &Bool), Some(Bool));
+        assert_eq!(union_primitive(&String, &Null), Some(String));
+        assert_eq!(union_primitive(&String, &F64), None);
+        assert_eq!(union_primitive(&Bool, &U64), None);
+    }
+
+    #[test]
+    fn test_shape_union() {
+        let a = shape(
+            r#"{
+                "class": "string",
+                "rank": "i64",
+                "info": { "name": "string", "value": "u64" },
+                "tags": ["string"],
+                "points": [{ "x": "i64" }],
+                "empty": [],
+                "maybe": "null"
+            }"#,
+        );
+        let b = shape(
+            r#"{
+                "rank": "f64",
+                "visible": "bool",
+                "info": { "value": "i64", "note": "string" },
+                "tags": [],
+                "points": [{ "x": "f32", "y": "f32" }],
+                "empty": ["u64"],
+                "maybe": { "deep": "bool" }
+            }"#,
+        );
+        let expected = shape(
+            r#"{
+                "class": "string",
+                "rank": "f64",
+                "visible": "bool",
+                "info": { "name": "string", "value": "i64", "note": "string" },
+                "tags": ["string"],
+                "points": [{ "x": "f64", "y": "f32" }],
+                "empty": ["u64"],
+                "maybe": { "deep": "bool" }
+            }"#,
+        );
+        assert_eq!(shape_union(&a, &b), Ok(expected.clone()));
+        // commutative
+        assert_eq!(shape_union(&b, &a), Ok(expected));
+        assert_eq!(shape_union(&a, &Shape::new()), Ok(a.clone()));
+        assert_eq!(shape_union(&Shape::new(), &a), Ok(a));
+    }
+
+    #[test]
+    fn test_shape_from_value() {
+        let value: Value = serde_json::from_str(
+            r#"{
+                "name": "river",
+                "rank": 3,
+                "depth": -2.5,
+                "visible": true,
+                "nothing": null,
+                "info": { "id": -4 },
+                "tags": ["a", "b"],
+                "ranks": [1, -2, 3.5],
+                "points": [{ "x": 1 }, { "x": 2 }],
+                "empty": []
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            shape_from_value(&value),
+            Ok(shape(
+                r#"{
+                    "name": "string",
+                    "rank": "u64",
+                    "depth": "f32",
+                    "visible": "bool",
+                    "nothing": "null",
+                    "info": { "id": "i64" },
+                    "tags": ["string"],
+                    "ranks": ["f64"],
+                    "points": [{ "x": "u64" }],
+                    "empty": []
+                }"#
+            ))
+        );
+
+        let value: Value = serde_json::from_str(r#"{ "a": { "b": [1, "x"] } }"#).unwrap();
+        let err = shape_from_value(&value).unwrap_err();
+        assert_eq!(err.path, "a.b");
+    }
+
+    #[test]
+    fn test_shape_conflict() {
+        let a = shape(r#"{ "info": { "name": "string" } }"#);
+        let b = shape(r#"{ "info": { "name": { "first": "string" } } }"#);
+        let err = shape_union(&a, &b).unwrap_err();
+        assert_eq!(err.path, "info.name");
+        assert_eq!(err.ours, ShapeType::Primitive(PrimitiveShape::String));
+        assert_eq!(
+            err.to_string(),
+            "incompatible shape types at info.name: \"string\" and {\"first\":\"string\"}"
+        );
+
+        let conflict = |a: &str, b: &str| shape_union(&shape(a), &shape(b)).is_err();
+        assert!(conflict(r#"{ "a": "string" }"#, r#"{ "a": "f64" }"#));
+        assert!(conflict(r#"{ "a": "string" }"#, r#"{ "a": ["string"] }"#));
+        assert!(conflict(r#"{ "a": ["string"] }"#, r#"{ "a": ["bool"] }"#));
+        assert!(conflict(r#"{ "a": ["string"] }"#, r#"{ "a": [{ "b": "string" }] }"#));
+        assert!(conflict(r#"{ "a": [{ "b": "u64" }] }"#, r#"{ "a": [{ "b": "string" }] }"#));
+        assert!(conflict(r#"{ "a": { "b": "u64" } }"#, r#"{ "a": ["u64"] }"#));
+    }
+
+    #[test]
+    fn test_layer_union() {
+        let mut layer = LayerMetaData {
+            minzoom: 2,
+            maxzoom: 8,
+            draw_types: Vec::from([DrawType::Polygons]),
+            shape: shape(r#"{ "class": "string", "rank": "u64" }"#),
+            ..Default::default()
+        };
+        let other = LayerMetaData {
+            description: Some("water".into()),
+            minzoom: 0,
+            maxzoom: 5,
+            draw_types: Vec::from([DrawType::Lines, DrawType::Polygons]),
+            shape: shape(r#"{ "rank": "i64", "depth": "f64" }"#),
+            m_shape: Some(shape(r#"{ "speed": "f32" }"#)),
+        };
+        layer.union(&other).unwrap();
+        assert_eq!(
+            layer,
+            LayerMetaData {
+                description: Some("water".into()),
+                minzoom: 0,
+                maxzoom: 8,
+                draw_types: Vec::from([DrawType::Lines, DrawType::Polygons]),
+                shape: shape(r#"{ "class": "string", "rank": "i64", "depth": "f64" }"#),
+                m_shape: Some(shape(r#"{ "speed": "f32" }"#)),
+            }
+        );
+
+        let before = layer.clone();
+        let other = LayerMetaData {
+            maxzoom: 14,
+            m_shape: Some(shape(r#"{ "speed": "string" }"#)),
+            ..Default::default()
+        };
+        let err = layer.union(&other).unwrap_err();
+        assert_eq!(err.path, "mShape.speed");
+        assert_eq!(layer, before);
+    }
+
+    #[test]
+    fn test_add_layer_union() {
+        let mut builder = MetadataBuilder::default();
+        let layer = |shape_str: &str, minzoom, maxzoom| LayerMetaData {
+            minzoom,
+            maxzoom,
+            shape: shape(shape_str),
+            ..Default::default()
+        };
+        builder.add_layer("water", &layer(r#"{ "class": "string" }"#, 0, 5)).unwrap();
+        builder.add_layer("water", &layer(r#"{ "depth": "u64" }"#, 3, 10)).unwrap();
+        let err = builder.add_layer("water", &layer(r#"{ "class": { "a": "bool" } }"#, 0, 14));
+        assert_eq!(err.unwrap_err().path, "shape.class");
+        let meta = builder.commit();
+        assert_eq!(meta.layers["water"], layer(r#"{ "class": "string", "depth": "u64" }"#, 0, 10));
+        assert_eq!(meta.maxzoom, 10);
+        assert_eq!(
+            meta.vector_layers,
+            Vec::from([VectorLayer {
+                id: "water".into(),
+                description: None,
+                minzoom: Some(0),
+                maxzoom: Some(10),
+                fields: BTreeMap::from([
+                    ("class".into(), "String".into()),
+                    ("depth".into(), "Number".into()),
+                ]),
+            }])
+        );
+    }
+}
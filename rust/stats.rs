@@ -0,0 +1,244 @@
+use crate::{DrawType, MetadataBuilder};
+use alloc::{collections::BTreeMap, string::String};
+use serde::{Deserialize, Serialize};
+
+/// Tile count and encoded tile sizes of a single zoom
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct ZoomStats {
+    /// Number of tiles written
+    pub tiles: u64,
+    /// Sum of the tile sizes in bytes
+    pub bytes: u64,
+    /// Smallest tile in bytes
+    pub min_bytes: u64,
+    /// Largest tile in bytes
+    pub max_bytes: u64,
+    /// Average tile size in bytes
+    pub avg_bytes: f64,
+}
+impl ZoomStats {
+    /// Record a tile of `bytes` size
+    pub fn add_tile(&mut self, bytes: u64) {
+        self.min_bytes = if self.tiles == 0 { bytes } else { self.min_bytes.min(bytes) };
+        self.max_bytes = self.max_bytes.max(bytes);
+        self.tiles += 1;
+        self.bytes += bytes;
+        self.update_avg();
+    }
+
+    /// Combine the stats of another set of tiles at the same zoom
+    pub fn merge(&mut self, other: &ZoomStats) {
+        if other.tiles == 0 {
+            return;
+        }
+        self.min_bytes =
+            if self.tiles == 0 { other.min_bytes } else { self.min_bytes.min(other.min_bytes) };
+        self.max_bytes = self.max_bytes.max(other.max_bytes);
+        self.tiles += other.tiles;
+        self.bytes += other.bytes;
+        self.update_avg();
+    }
+
+    fn update_avg(&mut self) {
+        self.avg_bytes = if self.tiles == 0 { 0. } else { self.bytes as f64 / self.tiles as f64 };
+    }
+}
+
+/// Feature counts and encoded sizes of a single layer
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct LayerStats {
+    /// Number of features written
+    pub features: u64,
+    /// Sum of the encoded layer sizes in bytes across all tiles
+    pub bytes: u64,
+    /// Number of features written per draw type
+    pub geometry: BTreeMap<DrawType, u64>,
+}
+impl LayerStats {
+    /// Combine the stats of another set of tiles for the same layer
+    pub fn merge(&mut self, other: &LayerStats) {
+        self.features += other.features;
+        self.bytes += other.bytes;
+        for (draw_type, count) in &other.geometry {
+            *self.geometry.entry(*draw_type).or_default() += count;
+        }
+    }
+}
+
+/// # Detailed Tile Stats
+///
+/// ## Description
+/// Optional tile statistics for capacity planning, stored under the `detailed_tilestats`
+/// extension key so the `tilestats` layout of the spec is untouched.
+///
+/// Gathered by the [`MetadataBuilder`] once [`MetadataBuilder::enable_detailed_stats`] is called.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct DetailedTileStats {
+    /// Tile counts and sizes per zoom
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub zooms: BTreeMap<u8, ZoomStats>,
+    /// Feature counts and sizes per layer
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub layers: BTreeMap<String, LayerStats>,
+}
+impl DetailedTileStats {
+    /// Number of features written per draw type across all layers
+    pub fn geometry(&self) -> BTreeMap<DrawType, u64> {
+        let mut res = BTreeMap::new();
+        for layer in self.layers.values() {
+            for (draw_type, count) in &layer.geometry {
+                *res.entry(*draw_type).or_default() += count;
+            }
+        }
+        res
+    }
+
+    /// Combine the stats of another set of tiles
+    pub fn merge(&mut self, other: &DetailedTileStats) {
+        for (zoom, stats) in &other.zooms {
+            self.zooms.entry(*zoom).or_default().merge(stats);
+        }
+        for (name, stats) in &other.layers {
+            self.layers.entry(name.clone()).or_default().merge(stats);
+        }
+    }
+}
+
+impl MetadataBuilder {
+    /// Gather [`DetailedTileStats`] from now on.
+    ///
+    /// Once enabled, [`MetadataBuilder::add_feature`] counts features per layer and draw type,
+    /// while tile and layer sizes are recorded with [`MetadataBuilder::add_tile_size`] and
+    /// [`MetadataBuilder::add_layer_size`]. Without it those hooks do nothing.
+    pub fn enable_detailed_stats(&mut self) {
+        self.metadata.detailed_tilestats.get_or_insert_default();
+    }
+
+    /// Record the encoded size of a tile written at a zoom
+    pub fn add_tile_size(&mut self, zoom: u8, bytes: u64) {
+        if let Some(stats) = &mut self.metadata.detailed_tilestats {
+            stats.zooms.entry(zoom).or_default().add_tile(bytes);
+        }
+    }
+
+    /// Record the encoded size of a layer inside of a single tile
+    pub fn add_layer_size(&mut self, layer: &str, bytes: u64) {
+        if let Some(stats) = &mut self.metadata.detailed_tilestats {
+            stats.layers.entry(layer.into()).or_default().bytes += bytes;
+        }
+    }
+
+    /// Record `count` features of a draw type written to a layer. Called for you by
+    /// [`MetadataBuilder::add_feature`]
+    pub fn add_layer_features(&mut self, layer: &str, draw_type: DrawType, count: u64) {
+        if let Some(stats) = &mut self.metadata.detailed_tilestats {
+            let layer = stats.layers.entry(layer.into()).or_default();
+            layer.features += count;
+            *layer.geometry.entry(draw_type).or_default() += count;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Metadata, VectorFeature, VectorGeometry, VectorPoint};
+
+    #[test]
+    fn test_zoom_stats() {
+        let mut stats = ZoomStats::default();
+        stats.add_tile(100);
+        stats.add_tile(50);
+        stats.add_tile(300);
+        assert_eq!(
+            stats,
+            ZoomStats { tiles: 3, bytes: 450, min_bytes: 50, max_bytes: 300, avg_bytes: 150. }
+        );
+        let mut other = ZoomStats::default();
+        other.merge(&ZoomStats::default());
+        assert_eq!(other, ZoomStats::default());
+        other.add_tile(10);
+        other.merge(&stats);
+        assert_eq!(
+            other,
+            ZoomStats { tiles: 4, bytes: 460, min_bytes: 10, max_bytes: 300, avg_bytes: 115. }
+        );
+    }
+
+    #[test]
+    fn test_builder_stats() {
+        // nothing is gathered unless enabled
+        let mut builder = MetadataBuilder::default();
+        builder.add_tile_size(3, 100);
+        builder.add_layer_size("water", 100);
+        builder.add_layer_features("water", DrawType::Lines, 1);
+        assert_eq!(builder.commit().detailed_tilestats, None);
+
+        let mut builder = MetadataBuilder::default();
+        builder.enable_detailed_stats();
+        builder.add_tile_size(3, 100);
+        builder.add_tile_size(3, 200);
+        builder.add_tile_size(4, 80);
+        builder.add_layer_size("water", 120);
+        builder.add_layer_size("water", 30);
+        builder.add_layer_features("water", DrawType::Polygons, 10);
+        let feature: VectorFeature = VectorFeature {
+            geometry: VectorGeometry::new_point(VectorPoint::from_xy(0., 0.), None),
+            ..Default::default()
+        };
+        builder.add_feature("water", 3, &feature).unwrap();
+        builder.add_feature("roads", 3, &feature).unwrap();
+        let meta = builder.commit();
+        let stats = meta.detailed_tilestats.clone().unwrap();
+        assert_eq!(stats.zooms[&3].tiles, 2);
+        assert_eq!(stats.zooms[&3].avg_bytes, 150.);
+        assert_eq!(stats.zooms[&4].min_bytes, 80);
+        assert_eq!(
+            stats.layers["water"],
+            LayerStats {
+                features: 11,
+                bytes: 150,
+                geometry: BTreeMap::from([(DrawType::Points, 1), (DrawType::Polygons, 10)]),
+            }
+        );
+        assert_eq!(
+            stats.geometry(),
+            BTreeMap::from([(DrawType::Points, 2), (DrawType::Polygons, 10)])
+        );
+
+        // serialized under its own key, leaving tilestats untouched
+        let json = serde_json::to_string(&meta).unwrap();
+        assert!(json.contains(r#""tilestats":{"total":0,"0":0,"1":0,"2":0,"3":0,"4":0,"5":0}"#));
+        assert!(json.contains(
+            r#""detailed_tilestats":{"zooms":{"3":{"tiles":2,"bytes":300,"min_bytes":100,"max_bytes":200,"avg_bytes":150.0}"#
+        ));
+        assert!(json.contains(r#""roads":{"features":1,"bytes":0,"geometry":{"1":1}}"#));
+        let back: DetailedTileStats =
+            serde_json::from_str(&serde_json::to_string(&stats).unwrap()).unwrap();
+        assert_eq!(back, stats);
+        let json = serde_json::to_string(&Metadata::default()).unwrap();
+        assert!(!json.contains("detailed_tilestats"));
+    }
+
+    #[test]
+    fn test_merge_stats() {
+        let mut a = MetadataBuilder::default();
+        a.enable_detailed_stats();
+        let mut b = a.fork();
+        a.add_tile_size(2, 10);
+        a.add_layer_features("water", DrawType::Lines, 2);
+        b.add_tile_size(2, 30);
+        b.add_layer_features("water", DrawType::Lines, 3);
+        let stats = a.clone().merge(b.clone()).0.commit().detailed_tilestats.unwrap();
+        assert_eq!(stats, b.merge(a.clone()).0.commit().detailed_tilestats.unwrap());
+        assert_eq!(stats.zooms[&2].avg_bytes, 20.);
+        assert_eq!(stats.layers["water"].features, 5);
+
+        let mut meta = a.commit();
+        meta.merge(&meta.clone());
+        assert_eq!(meta.detailed_tilestats.unwrap().layers["water"].geometry[&DrawType::Lines], 4);
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{
+    BBox, FACES, Face, FaceBounds, LonLatBounds, MAX_ZOOM, Metadata, Scheme,
+    projection::{
+        face_uv_to_xyz, lat_to_wm_y, lon_lat_to_xyz, lon_to_wm_x, tile_index, uv_to_st,
+        xyz_to_face_uv, xyz_to_lon_lat,
+    },
+};
+use alloc::vec::Vec;
+
+/// Samples per edge when tracing the outline of the lon-lat bounds on a face
+const SAMPLES: usize = 256;
+
+impl Metadata {
+    /// Compute the tile bounds of every zoom in `minzoom..=maxzoom` from the lon-lat `bounds`.
+    ///
+    /// Web Mercator schemes fill `wmbounds`. S2 schemes fill the `s2bounds` of every face the
+    /// bounds intersect and set `faces` to those faces. Existing tile bounds are replaced while
+    /// `tilestats` is left alone, as the bounds only estimate which tiles exist. Bounds crossing
+    /// the antimeridian (`left > right`) are supported.
+    pub fn fill_tile_bounds(&mut self) {
+        if self.scheme.is_s2() {
+            self.fill_s2_bounds();
+        } else {
+            self.fill_wm_bounds();
+        }
+    }
+
+    fn fill_wm_bounds(&mut self) {
+        self.wmbounds.clear();
+        self.faces = Vec::from([Face::Face0]);
+        let BBox { left, bottom, right, top } = self.bounds;
+        if bottom > top {
+            return;
+        }
+        for zoom in self.minzoom..=self.maxzoom.min(MAX_ZOOM) {
+            let max = (1u64 << zoom) - 1;
+            let (x_min, x_max) = match left <= right {
+                true => (
+                    tile_index(lon_to_wm_x(left, zoom), zoom),
+                    tile_index(lon_to_wm_x(right, zoom), zoom),
+                ),
+                false => (0, max),
+            };
+            let (mut y_min, mut y_max) = (
+                tile_index(lat_to_wm_y(top, zoom), zoom),
+                tile_index(lat_to_wm_y(bottom, zoom), zoom),
+            );
+            if self.scheme == Scheme::Tms {
+                (y_min, y_max) = (max - y_max, max - y_min);
+            }
+            self.wmbounds.insert(zoom, BBox::new(x_min, y_min, x_max, y_max));
+        }
+    }
+
+    fn fill_s2_bounds(&mut self) {
+        self.s2bounds = FaceBounds::default();
+        self.faces.clear();
+        let BBox { left, bottom, right, top } = self.bounds;
+        if bottom > top {
+            return;
+        }
+        let boxes = match left <= right {
+            true => Vec::from([self.bounds]),
+            false => Vec::from([
+                BBox::new(left, bottom, 180., top),
+                BBox::new(-180., bottom, right, top),
+            ]),
+        };
+        for face in FACES {
+            let Some(st) = boxes.iter().filter_map(|b| face_st_bounds(face, b)).reduce(|a, b| {
+                BBox::new(
+                    a.left.min(b.left),
+                    a.bottom.min(b.bottom),
+                    a.right.max(b.right),
+                    a.top.max(b.top),
+                )
+            }) else {
+                continue;
+            };
+            self.faces.push(face);
+            for zoom in self.minzoom..=self.maxzoom.min(MAX_ZOOM) {
+                let scale = (1u64 << zoom) as f64;
+                let [left, bottom, right, top] =
+                    [st.left, st.bottom, st.right, st.top].map(|st| tile_index(st * scale, zoom));
+                self.s2bounds.get_mut(face).insert(zoom, BBox::new(left, bottom, right, top));
+            }
+        }
+    }
+}
+
+/// The `s` and `t` extent of the part of the lon-lat bounds on a face, or `None` if they don't
+/// intersect.
+///
+/// The extremes of `u` and `v` lie on the outline of the intersection, made of the outline of the
+/// bounds on the face and the outline of the face within the bounds.
+fn face_st_bounds(face: Face, bounds: &LonLatBounds) -> Option<BBox<f64>> {
+    let mut uv: Option<BBox<f64>> = None;
+    let mut extend = |(u, v): (f64, f64)| {
+        let b = uv.get_or_insert(BBox::new(u, v, u, v));
+        (b.left, b.bottom, b.right, b.top) =
+            (b.left.min(u), b.bottom.min(v), b.right.max(u), b.top.max(v));
+    };
+
+    let BBox { left, bottom, right, top } = *bounds;
+    let corners = [(left, bottom), (right, bottom), (right, top), (left, top), (left, bottom)];
+    for pair in corners.windows(2) {
+        let [(lon0, lat0), (lon1, lat1)] = [pair[0], pair[1]];
+        let curve = |t: f64| {
+            xyz_to_face_uv(face, lon_lat_to_xyz(lon0 + (lon1 - lon0) * t, lat0 + (lat1 - lat0) * t))
+        };
+        // meridians are straight on the face, but parallels curve between the samples
+        trace(curve, &mut extend, lat0 == lat1);
+    }
+
+    let corners = [(-1., -1.), (1., -1.), (1., 1.), (-1., 1.), (-1., -1.)];
+    for pair in corners.windows(2) {
+        let [(u0, v0), (u1, v1)] = [pair[0], pair[1]];
+        let curve = |t: f64| {
+            let (u, v) = (u0 + (u1 - u0) * t, v0 + (v1 - v0) * t);
+            let (lon, lat) = xyz_to_lon_lat(face_uv_to_xyz(face, u, v));
+            let inside = lon >= left && lon <= right && lat >= bottom && lat <= top;
+            inside.then_some((u, v))
+        };
+        trace(curve, &mut extend, false);
+    }
+
+    uv.map(|b| BBox::new(uv_to_st(b.left), uv_to_st(b.bottom), uv_to_st(b.right), uv_to_st(b.top)))
+}
+
+/// Visit the points of a curve over `0..=1` that are `Some`, including the points where the curve
+/// enters or leaves and, if `refine`, the local extremes between the samples
+fn trace(
+    curve: impl Fn(f64) -> Option<(f64, f64)>,
+    visit: &mut impl FnMut((f64, f64)),
+    refine: bool,
+) {
+    let at = |i: usize| i as f64 / SAMPLES as f64;
+    let points: Vec<_> = (0..=SAMPLES).map(|i| curve(at(i))).collect();
+    for (i, point) in points.iter().enumerate() {
+        let Some(point) = *point else { continue };
+        visit(point);
+        for j in [i.checked_sub(1), Some(i + 1)].into_iter().flatten() {
+            if points.get(j).is_some_and(Option::is_none) {
+                visit(bisect(&curve, at(i), at(j)));
+            }
+        }
+        if refine
+            && i > 0
+            && let (Some(prev), Some(Some(next))) = (points[i - 1], points.get(i + 1))
+        {
+            for (axis, sign) in [(0, 1.), (0, -1.), (1, 1.), (1, -1.)] {
+                let value = |p: (f64, f64)| sign * if axis == 0 { p.0 } else { p.1 };
+                if value(point) >= value(prev) && value(point) >= value(*next) {
+                    let extreme = maximize(|t| curve(t).map(value), at(i - 1), at(i + 1));
+                    curve(extreme).into_iter().for_each(&mut *visit);
+                }
+            }
+        }
+    }
+}
+
+/// The last point on the curve between `inside` (on the curve) and `outside` (off the curve)
+fn bisect(curve: impl Fn(f64) -> Option<(f64, f64)>, inside: f64, outside: f64) -> (f64, f64) {
+    let (mut inside, mut outside) = (inside, outside);
+    let mut point = curve(inside).unwrap_or_default();
+    for _ in 0..52 {
+        let mid = (inside + outside) / 2.;
+        match curve(mid) {
+            Some(p) => (inside, point) = (mid, p),
+            None => outside = mid,
+        }
+    }
+    point
+}
+
+/// Ternary search for the maximum of a function that is unimodal between `a` and `b`
+fn maximize(f: impl Fn(f64) -> Option<f64>, a: f64, b: f64) -> f64 {
+    let (mut a, mut b) = (a, b);
+    for _ in 0..64 {
+        let (m1, m2) = (a + (b - a) / 3., b - (b - a) / 3.);
+        match (f(m1), f(m2)) {
+            (Some(v1), Some(v2)) if v1 < v2 => a = m1,
+            _ => b = m2,
+        }
+    }
+    (a + b) / 2.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MapboxTileJSONMetadata;
+    use alloc::vec;
+
+    #[test]
+    fn test_fill_wm_bounds() {
+        let mut meta = Metadata {
+            scheme: Scheme::Xyz,
+            bounds: BBox::new(-180., -85.05, 180., 85.05),
+            minzoom: 0,
+            maxzoom: 2,
+            ..Default::default()
+        };
+        meta.fill_tile_bounds();
+        assert_eq!(meta.wmbounds[&0], BBox::new(0, 0, 0, 0));
+        assert_eq!(meta.wmbounds[&2], BBox::new(0, 0, 3, 3));
+        assert_eq!(meta.tilestats.total, 0);
+        assert_eq!(meta.faces, vec![Face::Face0]);
+
+        // the north east quarter
+        meta.bounds = BBox::new(10., 10., 170., 80.);
+        meta.fill_tile_bounds();
+        assert_eq!(meta.wmbounds[&2], BBox::new(2, 0, 3, 1));
+        meta.scheme = Scheme::Tms;
+        meta.fill_tile_bounds();
+        assert_eq!(meta.wmbounds[&2], BBox::new(2, 2, 3, 3));
+
+        // crossing the antimeridian
+        meta.scheme = Scheme::Xyz;
+        meta.bounds = BBox::new(170., -10., -170., 10.);
+        meta.fill_tile_bounds();
+        assert_eq!(meta.wmbounds[&2], BBox::new(0, 1, 3, 2));
+        assert!(meta.has_tile_wm(2, 0, 1));
+        assert!(!meta.has_tile_wm(2, 0, 0));
+    }
+
+    #[test]
+    fn test_fill_s2_bounds() {
+        let mut meta = Metadata {
+            scheme: Scheme::Fzxy,
+            bounds: BBox::new(-180., -90., 180., 90.),
+            minzoom: 0,
+            maxzoom: 3,
+            ..Default::default()
+        };
+        meta.fill_tile_bounds();
+        assert_eq!(meta.faces, FACES.to_vec());
+        for face in FACES {
+            assert_eq!(meta.s2bounds.get(face)[&3], BBox::new(0, 0, 7, 7));
+        }
+
+        // a small box in the middle of face 0
+        meta.bounds = BBox::new(-1., -1., 1., 1.);
+        meta.fill_tile_bounds();
+        assert_eq!(meta.faces, vec![Face::Face0]);
+        assert_eq!(meta.s2bounds.get(Face::Face0)[&1], BBox::new(0, 0, 1, 1));
+        assert_eq!(meta.s2bounds.get(Face::Face0)[&3], BBox::new(3, 3, 4, 4));
+        assert!(meta.s2bounds.get(Face::Face1).is_empty());
+
+        // the cap around the north pole is on face 2 only
+        meta.bounds = BBox::new(-180., 80., 180., 90.);
+        meta.fill_tile_bounds();
+        assert_eq!(meta.faces, vec![Face::Face2]);
+        assert_eq!(meta.s2bounds.get(Face::Face2)[&3], BBox::new(3, 3, 4, 4));
+
+        // crossing the antimeridian touches face 3 (lon 180) but not face 0 (lon 0)
+        meta.bounds = BBox::new(170., -10., -170., 10.);
+        meta.fill_tile_bounds();
+        assert_eq!(meta.faces, vec![Face::Face3]);
+    }
+
+    #[test]
+    fn test_fill_on_import() {
+        let mapbox = MapboxTileJSONMetadata {
+            tilejson: "3.0.0".into(),
+            bounds: Some(BBox::new(-180., -85.05, 180., 85.05)),
+            maxzoom: Some(1),
+            ..Default::default()
+        };
+        let meta = mapbox.to_metadata();
+        assert_eq!(meta.wmbounds[&1], BBox::new(0, 0, 1, 1));
+        assert_eq!(meta.tilestats.total, 0);
+
+        // without bounds nothing is filled, even if the spec has default bounds
+        let meta = MapboxTileJSONMetadata::default().to_metadata();
+        assert!(meta.wmbounds.is_empty());
+        let mapbox = MapboxTileJSONMetadata { bounds: None, ..mapbox };
+        assert!(mapbox.to_metadata().wmbounds.is_empty());
+    }
+}
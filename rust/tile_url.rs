@@ -0,0 +1,279 @@
+use crate::{Face, Metadata, Scheme};
+use alloc::{format, string::String};
+use core::fmt;
+
+/// Describes a single tile to request
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TileRequest {
+    /// The S2 face of the tile. Ignored for Web Mercator schemes
+    pub face: Face,
+    /// The zoom of the tile
+    pub zoom: u8,
+    /// The x position of the tile
+    pub x: u64,
+    /// The y position of the tile. Always described top-down (xyz), TMS flipping is handled for you
+    pub y: u64,
+    /// The time of the tile. Required for time sensitive schemes (`tfzxy` and `txyz`) and `{t}`
+    pub time: Option<i64>,
+}
+
+/// Reasons a tile URL could not be built
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TileUrlError {
+    /// The metadata has no `tiles` templates
+    NoTemplates,
+    /// A template is missing a placeholder the scheme requires
+    MissingPlaceholder {
+        /// The offending template
+        template: String,
+        /// The placeholder that was expected, e.g. `{face}`
+        placeholder: &'static str,
+    },
+    /// The scheme or a template is time sensitive but the request has no time
+    MissingTime,
+    /// The x or y position is outside of `2^zoom`
+    TileOutOfRange,
+}
+impl fmt::Display for TileUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TileUrlError::NoTemplates => f.write_str("metadata has no tile URL templates"),
+            TileUrlError::MissingPlaceholder { template, placeholder } => {
+                write!(f, "template \"{template}\" is missing the {placeholder} placeholder")
+            }
+            TileUrlError::MissingTime => f.write_str("time sensitive tile URL requires a time"),
+            TileUrlError::TileOutOfRange => f.write_str("tile position is outside of the zoom"),
+        }
+    }
+}
+
+impl Metadata {
+    /// Build the URL of a tile from the `tiles` templates.
+    ///
+    /// Supported placeholders are `{face}`, `{z}`, `{x}`, `{y}`, `{-y}`, `{t}` and `{quadkey}`.
+    /// `{y}` is flipped for the `tms` scheme while `{-y}` is always flipped. If the template does
+    /// not end with its own file extension, the metadata `extension` is appended. When multiple
+    /// templates exist, the template is chosen from the tile position so the same tile always
+    /// resolves to the same host.
+    pub fn tile_url(&self, request: TileRequest) -> Result<String, TileUrlError> {
+        let templates = self.tiles.as_deref().unwrap_or_default();
+        if templates.is_empty() {
+            return Err(TileUrlError::NoTemplates);
+        }
+        for template in templates {
+            check_template(template, self.scheme)?;
+        }
+        let TileRequest { face, zoom, x, y, time } = request;
+        if zoom > 63 || x >> zoom != 0 || y >> zoom != 0 {
+            return Err(TileUrlError::TileOutOfRange);
+        }
+        let temporal = self.scheme.is_temporal() || templates.iter().any(|t| t.contains("{t}"));
+        if temporal && time.is_none() {
+            return Err(TileUrlError::MissingTime);
+        }
+
+        let face_id = if self.scheme.is_s2() { u8::from(face) as u64 } else { 0 };
+        let len = templates.len() as u64;
+        let index = (face_id % len + x % len + y % len) % len;
+        let template = &templates[index as usize];
+
+        let flipped_y = (1_u64 << zoom) - 1 - y;
+        let y_value = if self.scheme == Scheme::Tms { flipped_y } else { y };
+        let mut url = template
+            .replace("{face}", &format!("{face_id}"))
+            .replace("{z}", &format!("{zoom}"))
+            .replace("{x}", &format!("{x}"))
+            .replace("{y}", &format!("{y_value}"))
+            .replace("{-y}", &format!("{flipped_y}"))
+            .replace("{quadkey}", &quadkey(zoom, x, y));
+        if let Some(time) = time {
+            url = url.replace("{t}", &format!("{time}"));
+        }
+
+        Ok(append_extension(template, url, &self.extension))
+    }
+}
+
+/// Ensure the template has every placeholder the scheme needs
+fn check_template(template: &str, scheme: Scheme) -> Result<(), TileUrlError> {
+    let missing = |placeholder: &'static str| TileUrlError::MissingPlaceholder {
+        template: template.into(),
+        placeholder,
+    };
+    let has_quadkey = template.contains("{quadkey}");
+    if scheme.is_s2() && !template.contains("{face}") {
+        return Err(missing("{face}"));
+    }
+    if !has_quadkey {
+        if !template.contains("{z}") {
+            return Err(missing("{z}"));
+        }
+        if !template.contains("{x}") {
+            return Err(missing("{x}"));
+        }
+        if !template.contains("{y}") && !template.contains("{-y}") {
+            return Err(missing("{y}"));
+        }
+    }
+    if scheme.is_temporal() && !template.contains("{t}") {
+        return Err(missing("{t}"));
+    }
+    Ok(())
+}
+
+/// Bing style quadkey of a tile
+fn quadkey(zoom: u8, x: u64, y: u64) -> String {
+    let mut key = String::with_capacity(zoom as usize);
+    for i in (0..zoom).rev() {
+        let digit = ((x >> i) & 1) + 2 * ((y >> i) & 1);
+        key.push((b'0' + digit as u8) as char);
+    }
+    key
+}
+
+/// Append the extension if the template path does not end with one of its own
+fn append_extension(template: &str, mut url: String, extension: &str) -> String {
+    if extension.is_empty() {
+        return url;
+    }
+    let template_path = template.split('?').next().unwrap_or(template);
+    if !template_path.ends_with('}') {
+        return url;
+    }
+    match url.find('?') {
+        Some(query) => url.insert_str(query, &format!(".{extension}")),
+        None => url.push_str(&format!(".{extension}")),
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn meta(scheme: Scheme, tiles: &[&str]) -> Metadata {
+        Metadata {
+            scheme,
+            tiles: Some(tiles.iter().map(|t| t.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fzxy() {
+        let meta = meta(Scheme::Fzxy, &["https://tiles.org/{face}/{z}/{x}/{y}"]);
+        let url = meta.tile_url(TileRequest { face: Face::Face3, zoom: 2, x: 1, y: 3, time: None });
+        assert_eq!(url, Ok("https://tiles.org/3/2/1/3.pbf".into()));
+    }
+
+    #[test]
+    fn test_tfzxy() {
+        let meta = meta(Scheme::Tfzxy, &["https://tiles.org/{t}/{face}/{z}/{x}/{y}.mvt?k=1"]);
+        let req = TileRequest { face: Face::Face1, zoom: 1, x: 0, y: 1, time: Some(1_500) };
+        assert_eq!(meta.tile_url(req), Ok("https://tiles.org/1500/1/1/0/1.mvt?k=1".into()));
+        let req = TileRequest { time: None, ..req };
+        assert_eq!(meta.tile_url(req), Err(TileUrlError::MissingTime));
+        // a template asking for the time needs one even if the scheme does not
+        let meta = self::meta(Scheme::Fzxy, &["https://tiles.org/{t}/{face}/{z}/{x}/{y}"]);
+        assert_eq!(meta.tile_url(req), Err(TileUrlError::MissingTime));
+        assert_eq!(
+            meta.tile_url(req).unwrap_err().to_string(),
+            "time sensitive tile URL requires a time"
+        );
+        let req = TileRequest { time: Some(3), ..req };
+        assert_eq!(meta.tile_url(req), Ok("https://tiles.org/3/1/1/0/1.pbf".into()));
+    }
+
+    #[test]
+    fn test_xyz_and_txyz() {
+        let meta = meta(Scheme::Xyz, &["https://tiles.org/{z}/{x}/{y}?key=abc"]);
+        let req = TileRequest { face: Face::Face4, zoom: 3, x: 5, y: 2, time: None };
+        assert_eq!(meta.tile_url(req), Ok("https://tiles.org/3/5/2.pbf?key=abc".into()));
+
+        let mut meta = self::meta(Scheme::Txyz, &["https://tiles.org/{t}/{z}/{x}/{-y}.png"]);
+        meta.extension = "png".into();
+        let req = TileRequest { time: Some(7), ..req };
+        assert_eq!(meta.tile_url(req), Ok("https://tiles.org/7/3/5/5.png".into()));
+    }
+
+    #[test]
+    fn test_tms() {
+        let meta = meta(Scheme::Tms, &["https://tiles.org/{z}/{x}/{y}/{-y}"]);
+        let req = TileRequest { zoom: 2, x: 1, y: 0, ..Default::default() };
+        assert_eq!(meta.tile_url(req), Ok("https://tiles.org/2/1/3/3.pbf".into()));
+    }
+
+    #[test]
+    fn test_quadkey() {
+        assert_eq!(quadkey(0, 0, 0), "");
+        assert_eq!(quadkey(3, 3, 5), "213");
+        let meta = meta(Scheme::Xyz, &["https://tiles.org/{quadkey}.jpeg"]);
+        let req = TileRequest { zoom: 3, x: 3, y: 5, ..Default::default() };
+        assert_eq!(meta.tile_url(req), Ok("https://tiles.org/213.jpeg".into()));
+    }
+
+    #[test]
+    fn test_host_rotation() {
+        let meta = meta(
+            Scheme::Xyz,
+            &[
+                "https://a.org/{z}/{x}/{y}",
+                "https://b.org/{z}/{x}/{y}",
+                "https://c.org/{z}/{x}/{y}",
+            ],
+        );
+        let url = |x, y| meta.tile_url(TileRequest { zoom: 4, x, y, ..Default::default() });
+        assert_eq!(url(0, 0), Ok("https://a.org/4/0/0.pbf".into()));
+        assert_eq!(url(1, 0), Ok("https://b.org/4/1/0.pbf".into()));
+        assert_eq!(url(1, 1), Ok("https://c.org/4/1/1.pbf".into()));
+        assert_eq!(url(2, 1), Ok("https://a.org/4/2/1.pbf".into()));
+        // deterministic
+        assert_eq!(url(2, 1), url(2, 1));
+
+        // positions at the deepest zoom do not overflow
+        let max = (1 << 63) - 1;
+        let req = TileRequest { face: Face::Face5, zoom: 63, x: max, y: max, time: None };
+        let meta = self::meta(
+            Scheme::Fzxy,
+            &["https://a.org/{face}/{z}/{x}/{y}", "https://b.org/{face}/{z}/{x}/{y}"],
+        );
+        assert_eq!(meta.tile_url(req), Ok(format!("https://b.org/5/63/{max}/{max}.pbf")));
+    }
+
+    #[test]
+    fn test_errors() {
+        let req = TileRequest { zoom: 1, x: 1, y: 1, ..Default::default() };
+        assert_eq!(meta(Scheme::Xyz, &[]).tile_url(req), Err(TileUrlError::NoTemplates));
+        assert_eq!(
+            Metadata::default().tile_url(req).unwrap_err().to_string(),
+            "metadata has no tile URL templates"
+        );
+        let err = meta(Scheme::Fzxy, &["https://tiles.org/{z}/{x}/{y}"]).tile_url(req);
+        assert_eq!(
+            err,
+            Err(TileUrlError::MissingPlaceholder {
+                template: "https://tiles.org/{z}/{x}/{y}".into(),
+                placeholder: "{face}"
+            })
+        );
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "template \"https://tiles.org/{z}/{x}/{y}\" is missing the {face} placeholder"
+        );
+        // every template is checked, not just the one selected
+        let err = meta(Scheme::Xyz, &["https://a.org/{z}/{x}/{y}", "https://b.org/{z}/{x}"])
+            .tile_url(req);
+        assert!(matches!(err, Err(TileUrlError::MissingPlaceholder { placeholder: "{y}", .. })));
+        let err = meta(Scheme::Txyz, &["https://a.org/{z}/{x}/{y}"]).tile_url(req);
+        assert!(matches!(err, Err(TileUrlError::MissingPlaceholder { placeholder: "{t}", .. })));
+        let err = meta(Scheme::Xyz, &["https://a.org/{x}/{y}"]).tile_url(req);
+        assert!(matches!(err, Err(TileUrlError::MissingPlaceholder { placeholder: "{z}", .. })));
+        let err = meta(Scheme::Xyz, &["https://a.org/{z}/{y}"]).tile_url(req);
+        assert!(matches!(err, Err(TileUrlError::MissingPlaceholder { placeholder: "{x}", .. })));
+
+        let meta = meta(Scheme::Xyz, &["https://a.org/{z}/{x}/{y}"]);
+        let req = TileRequest { zoom: 1, x: 2, y: 0, ..Default::default() };
+        assert_eq!(meta.tile_url(req), Err(TileUrlError::TileOutOfRange));
+    }
+}
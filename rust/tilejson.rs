@@ -0,0 +1,224 @@
+use crate::{BBox, LonLatBounds, MapboxTileJSONMetadata, Scheme, SourceType};
+use alloc::string::String;
+
+/// The legacy TileJSON spec versions, grouped by the defaults they define
+///
+/// ## Links
+/// [TileJSON Specs](https://github.com/mapbox/tilejson-spec)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TileJSONVersion {
+    /// TileJSON 1.0.0. Raster tiles only, interactivity is described by a JavaScript `formatter`
+    V1_0,
+    /// TileJSON 2.0.x. Adds UTFGrid `grids` and the mustache `template` replacing `formatter`
+    V2_0,
+    /// TileJSON 2.1.0. Adds `data`
+    V2_1,
+    /// TileJSON 2.2.0
+    V2_2,
+    /// TileJSON 3.0.0. Adds the required `vector_layers` and `fillzoom`
+    V3_0,
+}
+impl TileJSONVersion {
+    /// Parse a `tilejson` version. Unknown minor versions fall back to the closest known
+    /// version of the same major, and any version past 3 is read as 3.0.0
+    pub fn parse(version: &str) -> Option<TileJSONVersion> {
+        let mut parts = version.trim().split('.');
+        let major: u32 = parts.next()?.parse().ok()?;
+        let minor: u32 = parts.next().map_or(Some(0), |minor| minor.parse().ok())?;
+        match (major, minor) {
+            (0, _) => None,
+            (1, _) => Some(TileJSONVersion::V1_0),
+            (2, 0) => Some(TileJSONVersion::V2_0),
+            (2, 1) => Some(TileJSONVersion::V2_1),
+            (2, _) => Some(TileJSONVersion::V2_2),
+            _ => Some(TileJSONVersion::V3_0),
+        }
+    }
+
+    /// The scheme used when `scheme` is absent. Every version defaults to `xyz`
+    pub fn default_scheme(&self) -> Scheme {
+        Scheme::Xyz
+    }
+
+    /// The zoom used when `maxzoom` is absent. `22` before 3.0.0 and `30` after
+    pub fn default_maxzoom(&self) -> u8 {
+        match self {
+            TileJSONVersion::V3_0 => 30,
+            _ => 22,
+        }
+    }
+
+    /// The bounds used when `bounds` is absent. The whole world before 3.0.0 and the Web Mercator
+    /// extent after
+    pub fn default_bounds(&self) -> LonLatBounds {
+        match self {
+            TileJSONVersion::V3_0 => BBox::new(-180., -85.05112877980659, 180., 85.0511287798066),
+            _ => BBox::new(-180., -90., 180., 90.),
+        }
+    }
+
+    /// Whether the version can describe vector tiles. Older versions only describe raster tiles
+    pub fn has_vector_layers(&self) -> bool {
+        *self >= TileJSONVersion::V3_0
+    }
+}
+
+impl MapboxTileJSONMetadata {
+    /// The spec version of the `tilejson` key. `None` if it is missing (e.g. tippecanoe metadata)
+    /// or invalid
+    pub fn spec_version(&self) -> Option<TileJSONVersion> {
+        TileJSONVersion::parse(&self.tilejson)
+    }
+
+    /// The scheme of the tiles. An explicit `scheme` is kept, except that `xyz` URLs with a `{t}`
+    /// placeholder become `txyz`. Otherwise the scheme is sniffed from the `{face}` and `{t}`
+    /// placeholders of `tiles`, falling back to the spec default of `xyz`
+    pub fn infer_scheme(&self) -> Scheme {
+        let sniffed = Scheme::from_tiles(&self.tiles);
+        match (self.scheme, sniffed) {
+            (Some(Scheme::Xyz), Some(Scheme::Txyz)) => Scheme::Txyz,
+            (Some(scheme), _) => scheme,
+            (None, Some(sniffed)) => sniffed,
+            (None, None) => self.spec_version().unwrap_or(TileJSONVersion::V3_0).default_scheme(),
+        }
+    }
+
+    /// The source type and extension, falling back to what the spec version describes when absent.
+    ///
+    /// Manifests older than 3.0.0 without `vector_layers` describe raster tiles, so the extension
+    /// is read from the `tiles` URL templates.
+    pub(crate) fn source_type_and_extension(&self) -> (SourceType, String) {
+        let is_raster = self.spec_version().is_some_and(|v| !v.has_vector_layers())
+            && self.vector_layers.is_empty();
+        match (self.r#type, &self.extension) {
+            (Some(r#type), Some(extension)) => (r#type, extension.clone()),
+            (r#type, extension) if is_raster => (
+                r#type.unwrap_or(SourceType::Raster),
+                extension.clone().or_else(|| self.tiles_extension()).unwrap_or("png".into()),
+            ),
+            (r#type, extension) => {
+                (r#type.unwrap_or_default(), extension.clone().unwrap_or("pbf".into()))
+            }
+        }
+    }
+
+    /// The file extension of the first `tiles` URL template, ignoring any query string
+    fn tiles_extension(&self) -> Option<String> {
+        let url = self.tiles.first()?;
+        let path = url.split(['?', '#']).next()?;
+        let file = path.rsplit('/').next()?;
+        let (_, extension) = file.rsplit_once('.')?;
+        let valid = !extension.is_empty() && extension.bytes().all(|b| b.is_ascii_alphanumeric());
+        valid.then(|| extension.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Face, Metadata, Projection, UnknownMetadata};
+    use alloc::vec;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(TileJSONVersion::parse("1.0.0"), Some(TileJSONVersion::V1_0));
+        assert_eq!(TileJSONVersion::parse("2.0.1"), Some(TileJSONVersion::V2_0));
+        assert_eq!(TileJSONVersion::parse("2.1.0"), Some(TileJSONVersion::V2_1));
+        assert_eq!(TileJSONVersion::parse("2.2.0"), Some(TileJSONVersion::V2_2));
+        assert_eq!(TileJSONVersion::parse("2.3"), Some(TileJSONVersion::V2_2));
+        assert_eq!(TileJSONVersion::parse("3.0.0"), Some(TileJSONVersion::V3_0));
+        assert_eq!(TileJSONVersion::parse("4.0.0"), Some(TileJSONVersion::V3_0));
+        assert_eq!(TileJSONVersion::parse(""), None);
+        assert_eq!(TileJSONVersion::parse("0.9.0"), None);
+        assert_eq!(TileJSONVersion::parse("two"), None);
+    }
+
+    #[test]
+    fn test_tilejson_1_defaults() {
+        let meta: UnknownMetadata = serde_json::from_str(
+            r#"{
+                "tilejson": "1.0.0",
+                "name": "satellite",
+                "tiles": ["https://tiles.org/{z}/{x}/{y}.jpg?token=abc"],
+                "formatter": "function(options, data) { return data.name; }"
+            }"#,
+        )
+        .unwrap();
+        let meta: Metadata = meta.to_metadata();
+        assert_eq!(meta.scheme, Scheme::Xyz);
+        assert_eq!((meta.r#type, meta.extension.as_str()), (SourceType::Raster, "jpg"));
+        assert_eq!((meta.minzoom, meta.maxzoom), (0, 22));
+        assert_eq!(meta.bounds, BBox::new(-180., -90., 180., 90.));
+        assert!(meta.layers.is_empty());
+        assert!(meta.get_extra::<String>("formatter").is_some());
+    }
+
+    #[test]
+    fn test_tilejson_2_defaults() {
+        let mapbox: MapboxTileJSONMetadata = serde_json::from_str(
+            r#"{
+                "tilejson": "2.2.0",
+                "tiles": ["https://tiles.org/{z}/{x}/{y}@2x"],
+                "grids": ["https://tiles.org/{z}/{x}/{y}.grid.json"],
+                "template": "{{#__teaser__}}{{name}}{{/__teaser__}}",
+                "legend": "<b>Roads</b>",
+                "maxzoom": 18
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(mapbox.spec_version(), Some(TileJSONVersion::V2_2));
+        let meta = mapbox.to_metadata();
+        assert_eq!(meta.scheme, Scheme::Xyz);
+        assert_eq!((meta.r#type, meta.extension.as_str()), (SourceType::Raster, "png"));
+        assert_eq!((meta.minzoom, meta.maxzoom), (0, 18));
+        assert_eq!(meta.template, mapbox.template);
+        assert_eq!(meta.legend, mapbox.legend);
+        assert_eq!(meta.grids, mapbox.grids);
+
+        // a tms manifest keeps its scheme
+        let tms = MapboxTileJSONMetadata { scheme: Some(Scheme::Tms), ..mapbox };
+        assert_eq!(tms.to_metadata().scheme, Scheme::Tms);
+    }
+
+    #[test]
+    fn test_tilejson_3_defaults() {
+        let mapbox = MapboxTileJSONMetadata {
+            tilejson: "3.0.0".into(),
+            tiles: ["https://tiles.org/{z}/{x}/{y}.png".into()].into(),
+            ..Default::default()
+        };
+        let meta = mapbox.to_metadata();
+        assert_eq!(meta.scheme, Scheme::Xyz);
+        assert_eq!((meta.r#type, meta.extension.as_str()), (SourceType::Vector, "pbf"));
+        assert_eq!(meta.maxzoom, 30);
+        assert_eq!(meta.bounds, BBox::new(-180., -85.05112877980659, 180., 85.0511287798066));
+
+        // without a version the S2 zooms are kept but the scheme is still legacy
+        let meta = MapboxTileJSONMetadata::default().to_metadata();
+        assert_eq!((meta.scheme, meta.maxzoom), (Scheme::Xyz, 27));
+    }
+
+    #[test]
+    fn test_infer_scheme() {
+        let mapbox = |scheme: Option<Scheme>, url: &str| MapboxTileJSONMetadata {
+            tilejson: "2.2.0".into(),
+            scheme,
+            tiles: vec![url.into()],
+            ..Default::default()
+        };
+        assert_eq!(mapbox(None, "/{z}/{x}/{y}.png").infer_scheme(), Scheme::Xyz);
+        assert_eq!(mapbox(Some(Scheme::Tms), "/{z}/{x}/{y}.png").infer_scheme(), Scheme::Tms);
+        assert_eq!(mapbox(None, "/{t}/{z}/{x}/{y}.png").infer_scheme(), Scheme::Txyz);
+        assert_eq!(mapbox(Some(Scheme::Xyz), "/{t}/{z}/{x}/{y}").infer_scheme(), Scheme::Txyz);
+
+        let s2 = mapbox(None, "/{face}/{z}/{x}/{y}.pbf").to_metadata();
+        assert_eq!((s2.scheme, s2.scheme.projection()), (Scheme::Fzxy, Projection::S2));
+        assert_eq!(s2.faces.len(), 6);
+        let wm = mapbox(None, "/{z}/{x}/{y}.pbf").to_metadata();
+        assert_eq!((wm.scheme.projection(), wm.faces), (Projection::WG, vec![Face::Face0]));
+
+        // unknown schemes are rejected instead of read as tms
+        let err = serde_json::from_str::<MapboxTileJSONMetadata>(r#"{ "scheme": "zxy" }"#);
+        assert!(err.is_err());
+    }
+}
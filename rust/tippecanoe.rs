@@ -0,0 +1,382 @@
+use crate::{DrawType, LayerMetaData, LayersMetaData, PrimitiveShape, Shape, ShapeType};
+use alloc::{string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The geometry type tippecanoe reports for a layer
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TippecanoeGeometry {
+    /// Point or MultiPoint features
+    #[default]
+    Point,
+    /// LineString or MultiLineString features
+    LineString,
+    /// Polygon or MultiPolygon features
+    Polygon,
+}
+impl From<TippecanoeGeometry> for DrawType {
+    fn from(geometry: TippecanoeGeometry) -> Self {
+        match geometry {
+            TippecanoeGeometry::Point => DrawType::Points,
+            TippecanoeGeometry::LineString => DrawType::Lines,
+            TippecanoeGeometry::Polygon => DrawType::Polygons,
+        }
+    }
+}
+
+/// The value type tippecanoe reports for an attribute
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TippecanoeAttributeType {
+    /// Every value is a string
+    #[default]
+    String,
+    /// Every value is a number
+    Number,
+    /// Every value is a boolean
+    Boolean,
+    /// Every value is null
+    Null,
+    /// The values have different types
+    Mixed,
+}
+
+/// Stats of a single attribute of a tippecanoe layer
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct TippecanoeAttribute {
+    /// Name of the attribute
+    pub attribute: String,
+    /// Number of unique values
+    #[serde(default)]
+    pub count: u64,
+    /// Type of the values
+    #[serde(rename = "type", default)]
+    pub r#type: TippecanoeAttributeType,
+    /// A sample of the unique values
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<serde_json::Value>,
+    /// Smallest numeric value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Largest numeric value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+}
+impl TippecanoeAttribute {
+    /// The shape type of the attribute. Numbers become `u64` or `i64` when the sampled values
+    /// and the min/max are all integers, otherwise `f64`. Mixed values become `string` unless
+    /// every sampled value agrees on a type.
+    pub fn shape_type(&self) -> ShapeType {
+        let primitive = match self.r#type {
+            TippecanoeAttributeType::String => PrimitiveShape::String,
+            TippecanoeAttributeType::Boolean => PrimitiveShape::Bool,
+            TippecanoeAttributeType::Null => PrimitiveShape::Null,
+            TippecanoeAttributeType::Number => self.number_shape(),
+            TippecanoeAttributeType::Mixed => {
+                if !self.values.is_empty() && self.values.iter().all(|v| v.is_number()) {
+                    self.number_shape()
+                } else if !self.values.is_empty() && self.values.iter().all(|v| v.is_boolean()) {
+                    PrimitiveShape::Bool
+                } else {
+                    PrimitiveShape::String
+                }
+            }
+        };
+        ShapeType::Primitive(primitive)
+    }
+
+    fn number_shape(&self) -> PrimitiveShape {
+        let numbers: Vec<&serde_json::Value> =
+            self.values.iter().filter(|v| v.is_number()).collect();
+        let bounds = [self.min, self.max];
+        let integer = numbers.iter().all(|v| v.is_i64() || v.is_u64())
+            && bounds.iter().flatten().all(|n| *n == (*n as i64) as f64);
+        if numbers.is_empty() && bounds.iter().all(Option::is_none) || !integer {
+            PrimitiveShape::F64
+        } else if numbers.iter().any(|v| v.as_i64().is_some_and(|n| n < 0))
+            || bounds.iter().flatten().any(|n| *n < 0.)
+        {
+            PrimitiveShape::I64
+        } else {
+            PrimitiveShape::U64
+        }
+    }
+}
+
+/// Stats of a single tippecanoe layer
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TippecanoeLayerStats {
+    /// Name of the layer
+    pub layer: String,
+    /// Number of features in the layer
+    #[serde(default)]
+    pub count: u64,
+    /// Geometry type of the features
+    #[serde(default)]
+    pub geometry: TippecanoeGeometry,
+    /// Number of attributes in the layer
+    #[serde(default)]
+    pub attribute_count: u64,
+    /// Stats of each attribute. May be truncated by tippecanoe so can be shorter than
+    /// `attributeCount`
+    #[serde(default)]
+    pub attributes: Vec<TippecanoeAttribute>,
+}
+impl TippecanoeLayerStats {
+    /// The shape of the layer's properties
+    pub fn shape(&self) -> Shape {
+        let mut shape = Shape::new();
+        for attribute in &self.attributes {
+            shape.insert(attribute.attribute.clone(), attribute.shape_type());
+        }
+        shape
+    }
+
+    /// Convert to a [`LayerMetaData`] spanning the given zoom range
+    pub fn to_layer_metadata(&self, minzoom: u8, maxzoom: u8) -> LayerMetaData {
+        LayerMetaData {
+            description: None,
+            minzoom,
+            maxzoom,
+            draw_types: Vec::from([self.geometry.into()]),
+            shape: self.shape(),
+            m_shape: None,
+        }
+    }
+}
+
+/// # Tippecanoe Tile Stats
+///
+/// ## Description
+/// The `tilestats` object written by tippecanoe (the mapbox-geostats format). Stored in
+/// [`crate::Metadata::tippecanoe_tilestats`], apart from the S2 tile counts of
+/// [`crate::TileStatsMetadata`].
+///
+/// ## Usage
+/// - [`TippecanoeTileStats::to_layers`]: Convert the attribute info into [`LayersMetaData`]
+/// - [`TippecanoeTileStats::merge`]: Combine the stats of two tilesets
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TippecanoeTileStats {
+    /// Number of layers
+    pub layer_count: u64,
+    /// Stats of each layer
+    #[serde(default)]
+    pub layers: Vec<TippecanoeLayerStats>,
+}
+impl TippecanoeTileStats {
+    /// Convert every layer into a [`LayerMetaData`] spanning the given zoom range
+    pub fn to_layers(&self, minzoom: u8, maxzoom: u8) -> LayersMetaData {
+        self.layers
+            .iter()
+            .map(|layer| (layer.layer.clone(), layer.to_layer_metadata(minzoom, maxzoom)))
+            .collect()
+    }
+
+    /// Combine the stats of another tileset. Feature counts are summed, attributes are matched
+    /// by name with their min/max widened and their value samples unioned
+    pub fn merge(&mut self, other: &TippecanoeTileStats) {
+        for theirs in &other.layers {
+            let Some(ours) = self.layers.iter_mut().find(|l| l.layer == theirs.layer) else {
+                self.layers.push(theirs.clone());
+                continue;
+            };
+            ours.count += theirs.count;
+            for attribute in &theirs.attributes {
+                match ours.attributes.iter_mut().find(|a| a.attribute == attribute.attribute) {
+                    Some(existing) => merge_attribute(existing, attribute),
+                    None => ours.attributes.push(attribute.clone()),
+                }
+            }
+            ours.attribute_count = ours.attribute_count.max(ours.attributes.len() as u64);
+        }
+        self.layer_count = self.layers.len() as u64;
+    }
+}
+
+/// Refine layers inferred from legacy `fields` with the draw types and more precise attribute
+/// types of tippecanoe stats, adding any layer that only appears in the stats
+pub(crate) fn refine_layers(
+    layers: &mut LayersMetaData,
+    stats: &TippecanoeTileStats,
+    minzoom: u8,
+    maxzoom: u8,
+) {
+    for layer in &stats.layers {
+        match layers.get_mut(&layer.layer) {
+            Some(existing) => {
+                let draw_type = layer.geometry.into();
+                if !existing.draw_types.contains(&draw_type) {
+                    existing.draw_types.push(draw_type);
+                }
+                for attribute in &layer.attributes {
+                    existing.shape.insert(attribute.attribute.clone(), attribute.shape_type());
+                }
+            }
+            None => {
+                layers.insert(layer.layer.clone(), layer.to_layer_metadata(minzoom, maxzoom));
+            }
+        }
+    }
+}
+
+/// Union the values and widen the min/max of an attribute
+fn merge_attribute(ours: &mut TippecanoeAttribute, theirs: &TippecanoeAttribute) {
+    if ours.r#type != theirs.r#type {
+        ours.r#type = TippecanoeAttributeType::Mixed;
+    }
+    for value in &theirs.values {
+        if !ours.values.contains(value) {
+            ours.values.push(value.clone());
+        }
+    }
+    ours.count = ours.count.max(theirs.count).max(ours.values.len() as u64);
+    ours.min = match (ours.min, theirs.min) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    ours.max = match (ours.max, theirs.max) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    };
+}
+
+/// Returns true if a `tilestats` value has tippecanoe's layout rather than the S2 tile counts
+pub(crate) fn is_tippecanoe_tilestats(tilestats: &Value) -> bool {
+    tilestats.get("layerCount").is_some() || tilestats.get("layers").is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Metadata, TileStatsMetadata};
+    use alloc::vec;
+
+    const TILESTATS: &str = r#"{
+        "layerCount": 2,
+        "layers": [
+            {
+                "layer": "roads",
+                "count": 12,
+                "geometry": "LineString",
+                "attributeCount": 5,
+                "attributes": [
+                    { "attribute": "name", "count": 2, "type": "string", "values": ["A1", "B2"] },
+                    { "attribute": "lanes", "count": 2, "type": "number", "values": [1, 4], "min": 1, "max": 4 },
+                    { "attribute": "offset", "count": 2, "type": "number", "values": [-2, 3], "min": -2, "max": 3 },
+                    { "attribute": "speed", "count": 2, "type": "number", "values": [2.5, 30], "min": 2.5, "max": 30 },
+                    { "attribute": "oneway", "count": 2, "type": "mixed", "values": [true, "yes"] }
+                ]
+            },
+            {
+                "layer": "pois",
+                "count": 1,
+                "geometry": "Point",
+                "attributeCount": 1,
+                "attributes": [
+                    { "attribute": "open", "count": 1, "type": "boolean", "values": [true] }
+                ]
+            }
+        ]
+    }"#;
+
+    fn shape(json: &str) -> Shape {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_tippecanoe_tilestats() {
+        let stats: TippecanoeTileStats = serde_json::from_str(TILESTATS).unwrap();
+        assert_eq!(stats.layer_count, 2);
+        assert_eq!(stats.layers[0].geometry, TippecanoeGeometry::LineString);
+        assert_eq!(stats.layers[0].attributes[1].max, Some(4.));
+
+        let layers = stats.to_layers(0, 14);
+        assert_eq!(
+            layers["roads"],
+            LayerMetaData {
+                description: None,
+                minzoom: 0,
+                maxzoom: 14,
+                draw_types: vec![DrawType::Lines],
+                shape: shape(
+                    r#"{ "name": "string", "lanes": "u64", "offset": "i64", "speed": "f64", "oneway": "string" }"#
+                ),
+                m_shape: None,
+            }
+        );
+        assert_eq!(layers["pois"].draw_types, vec![DrawType::Points]);
+        assert_eq!(layers["pois"].shape, shape(r#"{ "open": "bool" }"#));
+
+        let json = serde_json::to_string(&stats).unwrap();
+        let back: TippecanoeTileStats = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, stats);
+    }
+
+    #[test]
+    fn test_apart_from_s2_tilestats() {
+        // the S2 tilestats ignore tippecanoe's keys
+        let tilestats: TileStatsMetadata = serde_json::from_str(TILESTATS).unwrap();
+        assert_eq!(tilestats, TileStatsMetadata::default());
+
+        // tippecanoe writes its stats under `tilestats`
+        let meta_str =
+            alloc::format!(r#"{{ "generator": "tippecanoe", "tilestats": {TILESTATS} }}"#);
+        let mut meta: Metadata = serde_json::from_str(&meta_str).unwrap();
+        assert_eq!(meta.tippecanoe_tilestats.as_ref().unwrap().layers.len(), 2);
+        assert_eq!(meta.tilestats, TileStatsMetadata::default());
+        assert_eq!(meta.get_extra::<TippecanoeTileStats>("tilestats"), None);
+
+        // the S2 counts keep the key once both are set
+        meta.tilestats.total = 4;
+        meta.tilestats.total_0 = 4;
+        let json = serde_json::to_string(&meta).unwrap();
+        assert!(json.contains(r#""tilestats":{"total":4,"0":4,"1":0,"2":0,"3":0,"4":0,"5":0}"#));
+        assert!(json.contains(r#""tippecanoe_tilestats":{"layerCount":2,"#));
+        let back: Metadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.tilestats, meta.tilestats);
+        assert_eq!(back.tippecanoe_tilestats, meta.tippecanoe_tilestats);
+
+        let meta = Metadata::default();
+        assert!(!serde_json::to_string(&meta).unwrap().contains("tippecanoe_tilestats"));
+        // invalid tilestats still fail the strict parse
+        assert!(serde_json::from_str::<Metadata>(r#"{ "tilestats": { "total": "x" } }"#).is_err());
+        assert!(serde_json::from_str::<Metadata>(r#"{ "tilestats": { "layers": 2 } }"#).is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut stats: TippecanoeTileStats = serde_json::from_str(TILESTATS).unwrap();
+        let other: TippecanoeTileStats = serde_json::from_str(
+            r#"{
+                "layerCount": 2,
+                "layers": [
+                    {
+                        "layer": "roads",
+                        "count": 3,
+                        "geometry": "LineString",
+                        "attributeCount": 2,
+                        "attributes": [
+                            { "attribute": "lanes", "count": 2, "type": "number", "values": [4, 6], "min": 4, "max": 6 },
+                            { "attribute": "name", "count": 1, "type": "number", "values": [7], "min": 7, "max": 7 }
+                        ]
+                    },
+                    { "layer": "water", "count": 5, "geometry": "Polygon" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        stats.merge(&other);
+        assert_eq!(stats.layer_count, 3);
+        let roads = &stats.layers[0];
+        assert_eq!(roads.count, 15);
+        assert_eq!(
+            roads.attributes[1].values,
+            vec![serde_json::json!(1), serde_json::json!(4), serde_json::json!(6)]
+        );
+        assert_eq!((roads.attributes[1].min, roads.attributes[1].max), (Some(1.), Some(6.)));
+        assert_eq!(roads.attributes[0].r#type, TippecanoeAttributeType::Mixed);
+        assert_eq!(stats.layers[2].layer, "water");
+    }
+}
@@ -0,0 +1,517 @@
+use crate::{Center, FACES, LonLatBounds, Metadata, TileBounds};
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// The maximum zoom level allowed by the spec
+pub const MAX_ZOOM: u8 = 30;
+
+/// How bad a [`ValidationIssue`] is
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The manifest breaks the spec and should be refused
+    Error,
+    /// The manifest is usable but likely describes the data incorrectly
+    Warning,
+}
+
+/// Machine readable code describing what went wrong
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationCode {
+    /// A version string does not match `^\d+\.\d+\.\d+\w?[\w\d]*$`
+    InvalidVersion,
+    /// A zoom is larger than [`MAX_ZOOM`]
+    ZoomOutOfRange,
+    /// A minzoom is larger than its maxzoom
+    InvertedZoomRange,
+    /// Lon-lat bounds fall outside of [-180,180]/[-90,90]
+    BoundsOutOfRange,
+    /// A bounding box has its left/bottom larger than its right/top
+    InvertedBounds,
+    /// A tile index is outside of `2^zoom`
+    TileOutOfRange,
+    /// A tile bounds zoom is outside of minzoom-maxzoom
+    TileZoomOutsideRange,
+    /// A face value is listed more than once
+    DuplicateFace,
+    /// A face has data in `s2bounds` or `tilestats` but is not listed in `faces`
+    FaceNotListed,
+    /// A face is listed in `faces` but neither `s2bounds` nor `tilestats` has data for it
+    FaceWithoutData,
+    /// The tilestats total is smaller than the sum of the per face totals
+    TileStatsMismatch,
+    /// A layer zoom range is outside of the global minzoom-maxzoom
+    LayerZoomOutOfRange,
+    /// The centerpoint is outside of the bounds or zoom range
+    CenterOutOfRange,
+}
+impl ValidationCode {
+    /// The snake_case string form of the code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ValidationCode::InvalidVersion => "invalid_version",
+            ValidationCode::ZoomOutOfRange => "zoom_out_of_range",
+            ValidationCode::InvertedZoomRange => "inverted_zoom_range",
+            ValidationCode::BoundsOutOfRange => "bounds_out_of_range",
+            ValidationCode::InvertedBounds => "inverted_bounds",
+            ValidationCode::TileOutOfRange => "tile_out_of_range",
+            ValidationCode::TileZoomOutsideRange => "tile_zoom_outside_range",
+            ValidationCode::DuplicateFace => "duplicate_face",
+            ValidationCode::FaceNotListed => "face_not_listed",
+            ValidationCode::FaceWithoutData => "face_without_data",
+            ValidationCode::TileStatsMismatch => "tilestats_mismatch",
+            ValidationCode::LayerZoomOutOfRange => "layer_zoom_out_of_range",
+            ValidationCode::CenterOutOfRange => "center_out_of_range",
+        }
+    }
+}
+
+/// A single problem found by [`Metadata::validate`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    /// JSON path to the offending value, e.g. `$.layers["roads"].minzoom`
+    pub path: String,
+    /// How bad the issue is
+    pub severity: Severity,
+    /// Machine readable code
+    pub code: ValidationCode,
+    /// Human readable explanation
+    pub message: String,
+}
+impl ValidationIssue {
+    /// Create an error level issue
+    pub fn error(path: String, code: ValidationCode, message: String) -> Self {
+        ValidationIssue { path, severity: Severity::Error, code, message }
+    }
+
+    /// Create a warning level issue
+    pub fn warning(path: String, code: ValidationCode, message: String) -> Self {
+        ValidationIssue { path, severity: Severity::Warning, code, message }
+    }
+
+    /// Returns true if the issue is an error
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{severity}[{}] {}: {}", self.code.as_str(), self.path, self.message)
+    }
+}
+
+impl Metadata {
+    /// Check the metadata against the S2-TileJSON spec. An empty list means the metadata is valid.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        // versions
+        check_version(&mut issues, "$.s2tilejson", &self.s2tilejson);
+        check_version(&mut issues, "$.version", &self.version);
+        if let Some(tilejson) = &self.tilejson {
+            check_version(&mut issues, "$.tilejson", tilejson);
+        }
+
+        // zooms
+        check_zoom(&mut issues, "$.minzoom", self.minzoom);
+        check_zoom(&mut issues, "$.maxzoom", self.maxzoom);
+        if let Some(fillzoom) = self.fillzoom {
+            check_zoom(&mut issues, "$.fillzoom", fillzoom);
+        }
+        if self.minzoom > self.maxzoom {
+            issues.push(ValidationIssue::error(
+                "$.minzoom".into(),
+                ValidationCode::InvertedZoomRange,
+                format!("minzoom {} is larger than maxzoom {}", self.minzoom, self.maxzoom),
+            ));
+        }
+
+        // lon-lat bounds and center
+        check_lon_lat_bounds(&mut issues, "$.bounds", &self.bounds);
+        self.check_center(&mut issues);
+
+        // tile bounds
+        for (zoom, bounds) in &self.wmbounds {
+            self.check_tile_bounds(&mut issues, format!("$.wmbounds[\"{zoom}\"]"), *zoom, bounds);
+        }
+        for face in FACES {
+            for (zoom, bounds) in self.s2bounds.get(face) {
+                let path = format!("$.s2bounds[\"{}\"][\"{zoom}\"]", u8::from(face));
+                self.check_tile_bounds(&mut issues, path, *zoom, bounds);
+            }
+        }
+
+        // faces and tilestats
+        self.check_faces(&mut issues);
+
+        // layers
+        for (name, layer) in &self.layers {
+            let path = format!("$.layers[\"{name}\"]");
+            self.check_layer_zooms(&mut issues, &path, Some(layer.minzoom), Some(layer.maxzoom));
+        }
+        for (i, layer) in self.vector_layers.iter().enumerate() {
+            let path = format!("$.vector_layers[{i}]");
+            self.check_layer_zooms(&mut issues, &path, layer.minzoom, layer.maxzoom);
+        }
+
+        issues
+    }
+
+    /// Returns true if [`Metadata::validate`] finds no errors. Warnings are ignored.
+    pub fn is_valid(&self) -> bool {
+        !self.validate().iter().any(ValidationIssue::is_error)
+    }
+
+    fn check_center(&self, issues: &mut Vec<ValidationIssue>) {
+        let Center { lon, lat, zoom } = self.centerpoint;
+        let LonLatBounds { left, bottom, right, top } = self.bounds;
+        if lon < left || lon > right || lat < bottom || lat > top {
+            issues.push(ValidationIssue::warning(
+                "$.centerpoint".into(),
+                ValidationCode::CenterOutOfRange,
+                format!("centerpoint [{lon}, {lat}] is outside of the bounds"),
+            ));
+        }
+        if zoom < self.minzoom || zoom > self.maxzoom {
+            issues.push(ValidationIssue::warning(
+                "$.centerpoint.zoom".into(),
+                ValidationCode::CenterOutOfRange,
+                format!(
+                    "centerpoint zoom {zoom} is outside of the zoom range {}-{}",
+                    self.minzoom, self.maxzoom
+                ),
+            ));
+        }
+    }
+
+    fn check_tile_bounds(
+        &self,
+        issues: &mut Vec<ValidationIssue>,
+        path: String,
+        zoom: u8,
+        bounds: &TileBounds,
+    ) {
+        if zoom > MAX_ZOOM {
+            issues.push(ValidationIssue::error(
+                path,
+                ValidationCode::ZoomOutOfRange,
+                format!("zoom {zoom} is larger than {MAX_ZOOM}"),
+            ));
+            return;
+        }
+        if zoom < self.minzoom || zoom > self.maxzoom {
+            issues.push(ValidationIssue::warning(
+                path.clone(),
+                ValidationCode::TileZoomOutsideRange,
+                format!(
+                    "zoom {zoom} is outside of the zoom range {}-{}",
+                    self.minzoom, self.maxzoom
+                ),
+            ));
+        }
+        let size = 1_u64 << zoom;
+        let TileBounds { left, bottom, right, top } = *bounds;
+        if left >= size || bottom >= size || right >= size || top >= size {
+            issues.push(ValidationIssue::error(
+                path.clone(),
+                ValidationCode::TileOutOfRange,
+                format!("tile indices must be smaller than {size} at zoom {zoom}"),
+            ));
+        }
+        if left > right || bottom > top {
+            issues.push(ValidationIssue::error(
+                path,
+                ValidationCode::InvertedBounds,
+                "left/bottom must not be larger than right/top".into(),
+            ));
+        }
+    }
+
+    fn check_faces(&self, issues: &mut Vec<ValidationIssue>) {
+        for (i, face) in self.faces.iter().enumerate() {
+            if self.faces[..i].contains(face) {
+                issues.push(ValidationIssue::warning(
+                    format!("$.faces[{i}]"),
+                    ValidationCode::DuplicateFace,
+                    format!("face {} is listed more than once", u8::from(*face)),
+                ));
+            }
+        }
+
+        let tracks_s2 =
+            FACES.iter().any(|f| !self.s2bounds.get(*f).is_empty() || self.tilestats.get(*f) > 0);
+        for face in FACES {
+            let id = u8::from(face);
+            let listed = self.faces.contains(&face);
+            if !self.s2bounds.get(face).is_empty() && !listed {
+                issues.push(ValidationIssue::error(
+                    format!("$.s2bounds[\"{id}\"]"),
+                    ValidationCode::FaceNotListed,
+                    format!("face {id} has s2bounds but is not listed in faces"),
+                ));
+            }
+            if self.tilestats.get(face) > 0 && !listed {
+                issues.push(ValidationIssue::error(
+                    format!("$.tilestats[\"{id}\"]"),
+                    ValidationCode::FaceNotListed,
+                    format!("face {id} has tiles but is not listed in faces"),
+                ));
+            }
+            if tracks_s2
+                && listed
+                && self.s2bounds.get(face).is_empty()
+                && self.tilestats.get(face) == 0
+            {
+                issues.push(ValidationIssue::warning(
+                    "$.faces".into(),
+                    ValidationCode::FaceWithoutData,
+                    format!("face {id} is listed but has no s2bounds or tilestats"),
+                ));
+            }
+        }
+
+        let face_sum: u64 = FACES.iter().map(|f| self.tilestats.get(*f)).sum();
+        if self.tilestats.total < face_sum {
+            issues.push(ValidationIssue::warning(
+                "$.tilestats.total".into(),
+                ValidationCode::TileStatsMismatch,
+                format!(
+                    "total {} is smaller than the sum of the faces {face_sum}",
+                    self.tilestats.total
+                ),
+            ));
+        }
+    }
+
+    fn check_layer_zooms(
+        &self,
+        issues: &mut Vec<ValidationIssue>,
+        path: &str,
+        minzoom: Option<u8>,
+        maxzoom: Option<u8>,
+    ) {
+        if let (Some(min), Some(max)) = (minzoom, maxzoom)
+            && min > max
+        {
+            issues.push(ValidationIssue::error(
+                format!("{path}.minzoom"),
+                ValidationCode::InvertedZoomRange,
+                format!("layer minzoom {min} is larger than maxzoom {max}"),
+            ));
+        }
+        if let Some(min) = minzoom
+            && min < self.minzoom
+        {
+            issues.push(ValidationIssue::error(
+                format!("{path}.minzoom"),
+                ValidationCode::LayerZoomOutOfRange,
+                format!("layer minzoom {min} is smaller than the tileset minzoom {}", self.minzoom),
+            ));
+        }
+        if let Some(max) = maxzoom
+            && max > self.maxzoom
+        {
+            issues.push(ValidationIssue::error(
+                format!("{path}.maxzoom"),
+                ValidationCode::LayerZoomOutOfRange,
+                format!("layer maxzoom {max} is larger than the tileset maxzoom {}", self.maxzoom),
+            ));
+        }
+    }
+}
+
+/// Check that a version string matches `^\d+\.\d+\.\d+\w?[\w\d]*$`
+pub fn is_valid_version(version: &str) -> bool {
+    let mut parts = version.splitn(3, '.');
+    let (Some(major), Some(minor), Some(rest)) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let patch_len = rest.bytes().take_while(u8::is_ascii_digit).count();
+    is_number(major)
+        && is_number(minor)
+        && patch_len > 0
+        && rest[patch_len..].bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+fn check_version(issues: &mut Vec<ValidationIssue>, path: &str, version: &str) {
+    if !is_valid_version(version) {
+        issues.push(ValidationIssue::error(
+            path.into(),
+            ValidationCode::InvalidVersion,
+            format!("\"{version}\" does not match the pattern `\\d+.\\d+.\\d+\\w?[\\w\\d]*`"),
+        ));
+    }
+}
+
+fn check_zoom(issues: &mut Vec<ValidationIssue>, path: &str, zoom: u8) {
+    if zoom > MAX_ZOOM {
+        issues.push(ValidationIssue::error(
+            path.into(),
+            ValidationCode::ZoomOutOfRange,
+            format!("zoom {zoom} is larger than {MAX_ZOOM}"),
+        ));
+    }
+}
+
+fn check_lon_lat_bounds(issues: &mut Vec<ValidationIssue>, path: &str, bounds: &LonLatBounds) {
+    let LonLatBounds { left, bottom, right, top } = *bounds;
+    let lon_ok = |lon: f64| (-180. ..=180.).contains(&lon);
+    let lat_ok = |lat: f64| (-90. ..=90.).contains(&lat);
+    if !lon_ok(left) || !lon_ok(right) || !lat_ok(bottom) || !lat_ok(top) {
+        issues.push(ValidationIssue::error(
+            path.into(),
+            ValidationCode::BoundsOutOfRange,
+            "longitudes must be within [-180, 180] and latitudes within [-90, 90]".into(),
+        ));
+    } else if left > right || bottom > top {
+        issues.push(ValidationIssue::error(
+            path.into(),
+            ValidationCode::InvertedBounds,
+            "bounds must be [west, south, east, north] and must not wrap the antimeridian".into(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BBox, Face, LayerMetaData, TileStatsMetadata, VectorLayer};
+    use alloc::{collections::BTreeMap, string::ToString, vec};
+
+    fn codes(issues: &[ValidationIssue]) -> Vec<(&str, ValidationCode)> {
+        issues.iter().map(|i| (i.path.as_str(), i.code)).collect()
+    }
+
+    #[test]
+    fn test_versions() {
+        assert!(is_valid_version("1.0.0"));
+        assert!(is_valid_version("10.20.30"));
+        assert!(is_valid_version("1.0.0a"));
+        assert!(is_valid_version("1.0.0beta_2"));
+        assert!(!is_valid_version("1.0"));
+        assert!(!is_valid_version("2"));
+        assert!(!is_valid_version("1.0.0-beta"));
+        assert!(!is_valid_version("a.0.0"));
+        assert!(!is_valid_version("1..0"));
+        assert!(!is_valid_version("1.0.x"));
+    }
+
+    #[test]
+    fn test_default_is_valid() {
+        let meta = Metadata::default();
+        assert_eq!(meta.validate(), vec![]);
+        assert!(meta.is_valid());
+    }
+
+    #[test]
+    fn test_validate_errors() {
+        let meta = Metadata {
+            s2tilejson: "1".into(),
+            version: "2".into(),
+            minzoom: 12,
+            maxzoom: 31,
+            bounds: BBox::new(-200., 0., 10., 10.),
+            centerpoint: Center { lon: 5., lat: 5., zoom: 12 },
+            faces: vec![Face::Face0, Face::Face0],
+            wmbounds: BTreeMap::from([(12, BBox::new(0, 0, 4096, 3))]),
+            tilestats: TileStatsMetadata { total: 1, total_0: 1, total_2: 3, ..Default::default() },
+            layers: BTreeMap::from([(
+                "water".into(),
+                LayerMetaData { minzoom: 14, maxzoom: 13, ..Default::default() },
+            )]),
+            vector_layers: vec![VectorLayer {
+                id: "water".into(),
+                minzoom: Some(2),
+                maxzoom: Some(13),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let issues = meta.validate();
+        assert_eq!(
+            codes(&issues),
+            vec![
+                ("$.s2tilejson", ValidationCode::InvalidVersion),
+                ("$.version", ValidationCode::InvalidVersion),
+                ("$.maxzoom", ValidationCode::ZoomOutOfRange),
+                ("$.bounds", ValidationCode::BoundsOutOfRange),
+                ("$.wmbounds[\"12\"]", ValidationCode::TileOutOfRange),
+                ("$.faces[1]", ValidationCode::DuplicateFace),
+                ("$.tilestats[\"2\"]", ValidationCode::FaceNotListed),
+                ("$.tilestats.total", ValidationCode::TileStatsMismatch),
+                ("$.layers[\"water\"].minzoom", ValidationCode::InvertedZoomRange),
+                ("$.vector_layers[0].minzoom", ValidationCode::LayerZoomOutOfRange),
+            ]
+        );
+        assert_eq!(issues[0].severity, Severity::Error);
+        assert_eq!(issues[5].severity, Severity::Warning);
+        assert!(!meta.is_valid());
+        assert_eq!(
+            issues[0].to_string(),
+            "error[invalid_version] $.s2tilejson: \"1\" does not match the pattern \
+             `\\d+.\\d+.\\d+\\w?[\\w\\d]*`"
+        );
+        let json = serde_json::to_string(&issues[5]).unwrap();
+        assert_eq!(
+            json,
+            r#"{"path":"$.faces[1]","severity":"warning","code":"duplicate_face","message":"face 0 is listed more than once"}"#
+        );
+    }
+
+    #[test]
+    fn test_validate_s2() {
+        let mut meta = Metadata {
+            minzoom: 0,
+            maxzoom: 5,
+            faces: vec![Face::Face1, Face::Face3],
+            ..Default::default()
+        };
+        meta.s2bounds.get_mut(Face::Face1).insert(5, BBox::new(22, 17, 22, 17));
+        meta.s2bounds.get_mut(Face::Face2).insert(2, BBox::new(3, 2, 1, 3));
+        meta.s2bounds.get_mut(Face::Face2).insert(7, BBox::new(0, 0, 1, 1));
+        meta.s2bounds.get_mut(Face::Face2).insert(31, BBox::new(0, 0, 1, 1));
+        let issues = meta.validate();
+        assert_eq!(
+            codes(&issues),
+            vec![
+                ("$.s2bounds[\"2\"][\"2\"]", ValidationCode::InvertedBounds),
+                ("$.s2bounds[\"2\"][\"7\"]", ValidationCode::TileZoomOutsideRange),
+                ("$.s2bounds[\"2\"][\"31\"]", ValidationCode::ZoomOutOfRange),
+                ("$.s2bounds[\"2\"]", ValidationCode::FaceNotListed),
+                ("$.faces", ValidationCode::FaceWithoutData),
+            ]
+        );
+        assert_eq!(issues[1].severity, Severity::Warning);
+        assert_eq!(issues[4].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_validate_center_and_bounds() {
+        let meta = Metadata {
+            minzoom: 2,
+            maxzoom: 4,
+            bounds: BBox::new(10., 0., -10., 10.),
+            centerpoint: Center { lon: 0., lat: 0., zoom: 0 },
+            tilejson: Some("3.0".into()),
+            fillzoom: Some(40),
+            ..Default::default()
+        };
+        assert_eq!(
+            codes(&meta.validate()),
+            vec![
+                ("$.tilejson", ValidationCode::InvalidVersion),
+                ("$.fillzoom", ValidationCode::ZoomOutOfRange),
+                ("$.bounds", ValidationCode::InvertedBounds),
+                ("$.centerpoint", ValidationCode::CenterOutOfRange),
+                ("$.centerpoint.zoom", ValidationCode::CenterOutOfRange),
+            ]
+        );
+    }
+}